@@ -1,4 +1,6 @@
-use clap::{command, Args, Parser, Subcommand, ValueEnum};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use course_manager::approvals::Grade;
+use course_manager::validation::MAX_SEMESTER;
 use enum_iterator::{all, Sequence};
 use tabled::{Style, Table};
 
@@ -13,18 +15,80 @@ use tabled::{Style, Table};
 pub(crate) struct Cli {
     #[command(subcommand)]
     pub(crate) command: Option<Commands>,
+    #[arg(
+        long = "lock-timeout",
+        help = "Seconds to wait for another instance to release the courses data",
+        default_value = "5",
+        global = true,
+        required = false
+    )]
+    pub(crate) lock_timeout: u64,
+    #[arg(
+        short = 'p',
+        long = "profile",
+        help = "Profile to use instead of the active one",
+        global = true,
+        required = false
+    )]
+    pub(crate) profile: Option<String>,
+    #[arg(
+        long = "availability",
+        help = "Whether the requirements in progress make the courses that require them available",
+        default_value = "approved",
+        global = true,
+        required = false
+    )]
+    pub(crate) availability: AvailabilityRule,
 }
 
 #[derive(Subcommand)]
 pub(crate) enum Commands {
     #[command(about = "Initialize the courses list")]
     Init(Init),
+    #[command(about = "Replace the courses list with a new version, keeping the approvals")]
+    Update(Update),
+    #[command(about = "Carry approvals between courses lists through an equivalences table")]
+    Equivalences(Equivalences),
+    #[command(about = "Compare two courses lists")]
+    Diff(Diff),
+    #[command(about = "Check a courses list for problems")]
+    Validate(Validate),
     #[command(about = "List and filter courses")]
     List(List),
+    #[command(about = "Show every detail of a series of courses")]
+    Show(Show),
+    #[command(about = "List the courses that require a course, directly or not")]
+    Dependents(Dependents),
+    #[command(about = "Explain why a course is blocked")]
+    Why(Why),
+    #[command(about = "Schedule the remaining courses semester by semester")]
+    Plan(Plan),
+    #[command(about = "Show how far along the curriculum the approvals are")]
+    Progress(Progress),
+    #[command(about = "Show the credit weighted average of the approved courses")]
+    Gpa(Gpa),
     #[command(about = "approve a series of courses")]
     Approve(Approve),
     #[command(about = "Reject a series of courses")]
     Reject(Reject),
+    #[command(about = "Mark a series of courses as in progress")]
+    Enroll(Enroll),
+    #[command(about = "Record a failed attempt at a series of courses")]
+    Fail(Fail),
+    #[command(about = "Drop a series of courses in progress")]
+    Withdraw(Withdraw),
+    #[command(about = "Upgrade the courses data to the current schema version")]
+    Migrate(Migrate),
+    #[command(about = "Manage the profiles, each one with its own courses list")]
+    Profile(Profile),
+    #[command(about = "Revert the last approval or rejection")]
+    Undo,
+    #[command(about = "Apply again the last reverted approval or rejection")]
+    Redo,
+    #[command(about = "Show the history of approvals and rejections")]
+    History(History),
+    #[command(about = "Manage the backups of the courses data")]
+    Backup(Backup),
 }
 
 #[derive(Args)]
@@ -43,6 +107,144 @@ pub(crate) struct Init {
         "}
     )]
     pub(crate) uri: String,
+    #[arg(
+        long = "allow-invalid",
+        help = "Initialize the courses list even if the validation finds errors",
+        required = false
+    )]
+    pub(crate) allow_invalid: bool,
+}
+
+#[derive(Args)]
+pub(crate) struct Update {
+    #[arg(
+        required = true,
+        help = "URI of the new version of the courses list, can be a local file or a https url"
+    )]
+    pub(crate) uri: String,
+    #[arg(
+        short = 'y',
+        long = "yes",
+        help = "apply the update without asking for confirmation",
+        required = false
+    )]
+    pub(crate) yes: bool,
+    #[arg(
+        short = 'e',
+        long = "equivalences",
+        help = "equivalences table used to carry the approvals to the new courses",
+        required = false
+    )]
+    pub(crate) equivalences: Option<String>,
+    #[arg(
+        long = "allow-invalid",
+        help = "Update the courses list even if the validation finds errors",
+        required = false
+    )]
+    pub(crate) allow_invalid: bool,
+}
+
+#[derive(Args)]
+pub(crate) struct Equivalences {
+    #[command(subcommand)]
+    pub(crate) command: EquivalencesCommands,
+}
+
+#[derive(Subcommand)]
+pub(crate) enum EquivalencesCommands {
+    #[command(about = "Approve the courses equivalent to the approved ones")]
+    Apply(EquivalencesApply),
+}
+
+#[derive(Args)]
+pub(crate) struct EquivalencesApply {
+    #[arg(
+        required = true,
+        help = "json file with the equivalences, for more info use --help",
+        long_help = indoc::indoc!{"
+        json file with the equivalences, a list of old courses codes and the new codes they map to, for example:
+        [
+            {\"from\": [\"A\", \"B\"], \"to\": [\"X\"]},
+            {\"from\": [\"C\"], \"to\": [\"Y\", \"Z\"]}
+        ]
+        the new courses are approved when all the old courses are approved
+        "}
+    )]
+    pub(crate) mapping_file: String,
+    #[arg(
+        long = "to",
+        help = "URI of a new courses list to update to, by default the equivalences are applied to the current list",
+        required = false
+    )]
+    pub(crate) to: Option<String>,
+    #[arg(
+        short = 'y',
+        long = "yes",
+        help = "apply the update without asking for confirmation, only used with --to",
+        required = false
+    )]
+    pub(crate) yes: bool,
+    #[arg(
+        long = "allow-invalid",
+        help = "Update the courses list even if the validation finds errors, only used with --to",
+        required = false
+    )]
+    pub(crate) allow_invalid: bool,
+}
+
+#[derive(Args)]
+pub(crate) struct Diff {
+    #[arg(
+        required = true,
+        help = "URI of the old courses list, can be a local file or a https url"
+    )]
+    pub(crate) old_uri: String,
+    #[arg(
+        required = true,
+        help = "URI of the new courses list, can be a local file or a https url"
+    )]
+    pub(crate) new_uri: String,
+    #[arg(
+        short = 'f',
+        long = "format",
+        help = "Format of the output",
+        default_value = "table",
+        required = false
+    )]
+    pub(crate) print_format: PrintFormat,
+    #[arg(
+        short = 't',
+        long = "table-format",
+        help = "Format of the table, only used if the format is table",
+        required = false,
+        default_value = "rounded"
+    )]
+    pub(crate) table_format: TableStyle,
+}
+
+#[derive(Args)]
+pub(crate) struct Validate {
+    #[arg(
+        required = true,
+        help = "URI of the courses list, can be a local file or a https url"
+    )]
+    pub(crate) uri: String,
+    #[arg(
+        short = 'f',
+        long = "format",
+        help = "Format of the output",
+        default_value = "table",
+        required = false
+    )]
+    pub(crate) print_format: PrintFormat,
+    #[arg(
+        short = 't',
+        long = "table-format",
+        help = "Format of the table, only used if the format is table",
+        required = false,
+        default_value = "rounded"
+    )]
+    pub(crate) table_format: TableStyle,
 }
 
 #[derive(Args)]
@@ -81,6 +283,152 @@ pub(crate) struct List {
     pub(crate) table_format: TableStyle,
 }
 
+#[derive(Args)]
+pub(crate) struct Show {
+    #[arg(help = "Codes of the courses", required = true)]
+    pub(crate) codes: Vec<String>,
+    #[arg(
+        short = 'f',
+        long = "format",
+        help = "Format of the output, plain prints one field per line",
+        default_value = "table",
+        required = false
+    )]
+    pub(crate) print_format: DetailFormat,
+    #[arg(
+        short = 't',
+        long = "table-format",
+        help = "Format of the table, only used if the format is table",
+        required = false,
+        default_value = "rounded"
+    )]
+    pub(crate) table_format: TableStyle,
+}
+
+#[derive(Args)]
+pub(crate) struct Dependents {
+    #[arg(help = "Code of the course", required = true)]
+    pub(crate) code: String,
+    #[arg(
+        short = 'f',
+        long = "format",
+        help = "Format of the output",
+        default_value = "table",
+        required = false
+    )]
+    pub(crate) print_format: PrintFormat,
+    #[arg(
+        short = 't',
+        long = "table-format",
+        help = "Format of the table, only used if the format is table",
+        required = false,
+        default_value = "rounded"
+    )]
+    pub(crate) table_format: TableStyle,
+}
+
+#[derive(Args)]
+pub(crate) struct Why {
+    #[arg(help = "Code of the course", required = true)]
+    pub(crate) code: String,
+    #[arg(
+        short = 'f',
+        long = "format",
+        help = "Format of the output, the table format shows a tree",
+        default_value = "table",
+        required = false
+    )]
+    pub(crate) print_format: PrintFormat,
+}
+
+#[derive(Args)]
+pub(crate) struct Plan {
+    #[arg(
+        long = "max-credits",
+        help = "Maximum credits to take in a single term",
+        required = true
+    )]
+    pub(crate) max_credits: u32,
+    #[arg(
+        long = "start-semester",
+        help = "Semester of the year of the first planned term, only the courses of that semester are offered in it",
+        default_value = "1",
+        value_parser = clap::value_parser!(u8).range(1..=MAX_SEMESTER as i64),
+        required = false
+    )]
+    pub(crate) start_semester: u8,
+    #[arg(
+        short = 'f',
+        long = "format",
+        help = "Format of the output",
+        default_value = "table",
+        required = false
+    )]
+    pub(crate) print_format: PrintFormat,
+    #[arg(
+        short = 't',
+        long = "table-format",
+        help = "Format of the table, only used if the format is table",
+        required = false,
+        default_value = "rounded"
+    )]
+    pub(crate) table_format: TableStyle,
+}
+
+#[derive(Args)]
+pub(crate) struct Progress {
+    #[arg(
+        short = 'f',
+        long = "format",
+        help = "Format of the output, the table format shows progress bars",
+        default_value = "table",
+        required = false
+    )]
+    pub(crate) print_format: PrintFormat,
+    #[arg(
+        short = 't',
+        long = "table-format",
+        help = "Format of the table, only used if the format is table",
+        required = false,
+        default_value = "rounded"
+    )]
+    pub(crate) table_format: TableStyle,
+}
+
+#[derive(Args)]
+pub(crate) struct Gpa {
+    #[arg(
+        short = 's',
+        long = "scale",
+        help = "Scale of the grades, letter grades are averaged from 0 to 4",
+        default_value = "ten",
+        required = false
+    )]
+    pub(crate) scale: GradeScale,
+    #[arg(
+        long = "include-failed",
+        help = "count the failed attempts, each recorded failure with its own grade and the other attempts before an approval with the lowest grade of the scale",
+        required = false
+    )]
+    pub(crate) include_failed: bool,
+    #[arg(
+        short = 'f',
+        long = "format",
+        help = "Format of the output",
+        default_value = "table",
+        required = false
+    )]
+    pub(crate) print_format: PrintFormat,
+    #[arg(
+        short = 't',
+        long = "table-format",
+        help = "Format of the table, only used if the format is table",
+        required = false,
+        default_value = "rounded"
+    )]
+    pub(crate) table_format: TableStyle,
+}
+
 #[derive(Args)]
 pub(crate) struct Approve {
     #[arg(help = "Courses to approve", required = true)]
@@ -103,6 +451,69 @@ pub(crate) struct Approve {
     )]
     #[clap(action = clap::ArgAction::Set)]
     pub(crate) force: bool,
+    #[arg(
+        long = "partial",
+        help = "approve the valid courses even if some of them can not be approved",
+        required = false
+    )]
+    pub(crate) partial: bool,
+    #[arg(
+        long = "dry-run",
+        help = "only report which courses would be approved, without writing anything",
+        required = false
+    )]
+    pub(crate) dry_run: bool,
+    #[arg(
+        long = "grade",
+        help = "grade obtained in the given courses, either a number from 0 to 100 or a letter",
+        value_parser = parse_grade,
+        required = false
+    )]
+    pub(crate) grade: Option<Grade>,
+    #[arg(
+        long = "term",
+        help = "term in which the given courses were taken, for example 2023-1",
+        required = false
+    )]
+    pub(crate) term: Option<String>,
+}
+
+#[derive(Args)]
+pub(crate) struct Enroll {
+    #[arg(help = "Courses to enroll in", required = true)]
+    pub(crate) courses: Vec<String>,
+    #[arg(
+        short = 'f',
+        long = "force",
+        help = "enroll in the courses even if they have unmet requirements",
+        required = false
+    )]
+    pub(crate) force: bool,
+}
+
+#[derive(Args)]
+pub(crate) struct Fail {
+    #[arg(help = "Courses that were failed", required = true)]
+    pub(crate) courses: Vec<String>,
+    #[arg(
+        long = "grade",
+        help = "grade obtained in the given courses, either a number from 0 to 100 or a letter",
+        value_parser = parse_grade,
+        required = false
+    )]
+    pub(crate) grade: Option<Grade>,
+    #[arg(
+        long = "term",
+        help = "term in which the given courses were taken, for example 2023-1",
+        required = false
+    )]
+    pub(crate) term: Option<String>,
+}
+
+#[derive(Args)]
+pub(crate) struct Withdraw {
+    #[arg(help = "Courses in progress to drop", required = true)]
+    pub(crate) courses: Vec<String>,
 }
 
 #[derive(Args)]
@@ -127,6 +538,98 @@ pub(crate) struct Reject {
     )]
     #[clap(action = clap::ArgAction::Set)]
     pub(crate) force: bool,
+    #[arg(
+        long = "dry-run",
+        help = "only report which courses would be rejected, without writing anything",
+        required = false
+    )]
+    pub(crate) dry_run: bool,
+}
+
+#[derive(Args)]
+pub(crate) struct Migrate {
+    #[arg(
+        long = "dry-run",
+        help = "only report what would change, without writing anything",
+        required = false
+    )]
+    pub(crate) dry_run: bool,
+}
+
+#[derive(Args)]
+pub(crate) struct History {
+    #[arg(
+        short = 't',
+        long = "table-format",
+        help = "Format of the table",
+        required = false,
+        default_value = "rounded"
+    )]
+    pub(crate) table_format: TableStyle,
+}
+
+#[derive(Args)]
+pub(crate) struct Backup {
+    #[command(subcommand)]
+    pub(crate) command: BackupCommands,
+    #[arg(
+        short = 't',
+        long = "table-format",
+        help = "Format of the table, used by list",
+        required = false,
+        default_value = "rounded",
+        global = true
+    )]
+    pub(crate) table_format: TableStyle,
+}
+
+#[derive(Subcommand)]
+pub(crate) enum BackupCommands {
+    #[command(about = "List the backups")]
+    List,
+    #[command(about = "Back up the current courses data")]
+    Create,
+    #[command(
+        about = "Replace the courses data with a backup, the current data is backed up first"
+    )]
+    Restore {
+        #[arg(help = "Name of the backup", required = true)]
+        name: String,
+    },
+}
+
+#[derive(Args)]
+pub(crate) struct Profile {
+    #[command(subcommand)]
+    pub(crate) command: ProfileCommands,
+}
+
+#[derive(Subcommand)]
+pub(crate) enum ProfileCommands {
+    #[command(about = "Create a new profile, it has to be initialized with init --profile")]
+    Create {
+        #[arg(help = "Name of the profile", required = true)]
+        name: String,
+    },
+    #[command(about = "List the profiles")]
+    List,
+    #[command(about = "Make a profile the active one")]
+    Switch {
+        #[arg(help = "Name of the profile", required = true)]
+        name: String,
+    },
+    #[command(about = "Delete a profile along with all its data")]
+    Delete {
+        #[arg(help = "Name of the profile", required = true)]
+        name: String,
+    },
+    #[command(about = "Rename a profile")]
+    Rename {
+        #[arg(help = "Current name of the profile", required = true)]
+        name: String,
+        #[arg(help = "New name of the profile", required = true)]
+        new_name: String,
+    },
 }
 
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
@@ -135,6 +638,14 @@ pub(crate) enum PrintFormat {
     Json,
     Raw,
 }
+/// output formats of the course details, which can also be printed as plain text
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub(crate) enum DetailFormat {
+    Table,
+    Json,
+    Raw,
+    Plain,
+}
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
 pub(crate) enum TableStyle {
     Ascii,
@@ -151,12 +662,48 @@ pub(crate) enum TableStyle {
     Empty,
 }
 
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub(crate) enum AvailabilityRule {
+    Approved,
+    InProgress,
+}
+
+impl From<AvailabilityRule> for course_manager::enrollment::AvailabilityRule {
+    fn from(rule: AvailabilityRule) -> Self {
+        match rule {
+            AvailabilityRule::Approved => course_manager::enrollment::AvailabilityRule::Approved,
+            AvailabilityRule::InProgress => {
+                course_manager::enrollment::AvailabilityRule::InProgress
+            }
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub(crate) enum GradeScale {
+    Ten,
+    Hundred,
+    Letter,
+}
+
+impl From<GradeScale> for course_manager::stats::GradeScale {
+    fn from(scale: GradeScale) -> Self {
+        match scale {
+            GradeScale::Ten => course_manager::stats::GradeScale::Ten,
+            GradeScale::Hundred => course_manager::stats::GradeScale::Hundred,
+            GradeScale::Letter => course_manager::stats::GradeScale::Letter,
+        }
+    }
+}
+
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, ValueEnum, Sequence)]
 pub(crate) enum CourseStatus {
     All,
     Blocked,
     Approved,
     Available,
+    InProgress,
+    Failed,
 }
 
 pub(crate) fn to_course_statuses(
@@ -182,15 +729,19 @@ pub(crate) fn to_course_statuses(
                                 CourseStatus::Available => {
                                     course_manager::courses::CourseStatus::Available
                                 }
+                                CourseStatus::InProgress => {
+                                    course_manager::courses::CourseStatus::InProgress
+                                }
+                                CourseStatus::Failed => {
+                                    course_manager::courses::CourseStatus::Failed
+                                }
                             };
                             // ignore any status that is already in the vector
                             if !course_statuses.contains(&course) {
                                 continue;
                             }
                             // and any course that is after the current parameter
-                            if status.iter().enumerate().any(|(j, fs)| {
-                                return j > i && fs == s;
-                            }) {
+                            if status.iter().enumerate().any(|(j, fs)| j > i && fs == s) {
                                 continue;
                             }
                             course_statuses.push(course);
@@ -205,6 +756,12 @@ pub(crate) fn to_course_statuses(
                     CourseStatus::Available => {
                         course_statuses.push(course_manager::courses::CourseStatus::Available)
                     }
+                    CourseStatus::InProgress => {
+                        course_statuses.push(course_manager::courses::CourseStatus::InProgress)
+                    }
+                    CourseStatus::Failed => {
+                        course_statuses.push(course_manager::courses::CourseStatus::Failed)
+                    }
                 }
             }
             Some(course_statuses)
@@ -229,3 +786,13 @@ pub(crate) fn to_table_style(table: &mut Table, style: TableStyle) -> &mut Table
         TableStyle::Empty => table.with(Style::empty()),
     }
 }
+
+/// parses a grade argument, refusing the grades that do not fit in any scale
+fn parse_grade(grade: &str) -> Result<Grade, String> {
+    grade.parse().map_err(|_| {
+        format!(
+            "{} is neither a number from 0 to 100 nor a letter from F to A+",
+            grade
+        )
+    })
+}
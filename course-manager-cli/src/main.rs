@@ -1,90 +1,99 @@
 mod cli;
 mod util;
+use std::time::Duration;
+
 use clap::{CommandFactory, Parser};
-use course_manager::{approve_courses, courses::to_str, reject_courses, requires_init};
+use course_manager::{
+    approvals::ApprovalDetails,
+    batch::BatchReport,
+    courses::{to_str, CourseStatus},
+    details::{CourseDetails, Dependent},
+    diff::diff_curricula,
+    equivalences::Equivalences,
+    error::Error,
+    explain::Explanation,
+    planner,
+    profiles::Profiles,
+    progress,
+    stats::Statistics,
+    validation::{has_errors, validate, Diagnostic},
+    CourseManager,
+};
 
-use cli::{to_course_statuses, Cli, Commands, PrintFormat};
-use spinoff::{spinners, Spinner};
+use cli::{
+    to_course_statuses, Backup, BackupCommands, Cli, Commands, Dependents, DetailFormat, Diff,
+    EquivalencesCommands, Gpa, Plan, PrintFormat, ProfileCommands, Progress, Show, Validate,
+};
 use tabled::Table;
-use util::CourseTable;
+use util::{
+    confirm, describe_grade, detail_fields, format_timestamp, gpa_row, load_courses,
+    load_equivalences, progress_row, render_tree, BackupTable, CourseTable, DependentTable,
+    DiagnosticTable, DiffTable, GpaTable, HistoryTable, PlanTable, ProgressTable,
+};
 
 use crate::cli::to_table_style;
 
 fn main() {
     let cli = Cli::parse();
+    let profiles = match Profiles::from_default_dir() {
+        Ok(profiles) => profiles,
+        Err(e) => {
+            println!("{:#?}", e);
+            return;
+        }
+    };
+    // the profiles are managed without opening the courses data
+    if let Some(Commands::Profile(profile)) = &cli.command {
+        manage_profiles(&profiles, &profile.command);
+        return;
+    }
+    let manager = match &cli.profile {
+        Some(name) => profiles.manager(name),
+        None => profiles.active_manager(),
+    };
+    let manager = match manager {
+        Ok(manager) => manager
+            .with_lock_timeout(Duration::from_secs(cli.lock_timeout))
+            .with_availability_rule(cli.availability.into()),
+        Err(e) => {
+            println!("{:#?}", e);
+            return;
+        }
+    };
     match &cli.command {
-        Some(Commands::Init(init_courses)) => {
-            // check if begins with https
-            match init_courses.uri.starts_with("https://") {
-                true => {
-                    // show a downloading spinner
-                    let sp = Spinner::new(spinners::Dots12, "Downloading courses list", None);
-                    // download the file
-                    match reqwest::blocking::get(&init_courses.uri) {
-                        Ok(response) => match response.text() {
-                            Ok(text) => {
-                                let courses = course_manager::get_courses_from_json(text);
-                                match courses {
-                                    Ok(courses) => {
-                                        match course_manager::initialize_courses(courses) {
-                                            Ok(_) => {
-                                                sp.success("courses initialized successfully");
-                                            }
-                                            Err(e) => {
-                                                sp.fail(&format!(
-                                                    "could not initialize courses: {:#?}",
-                                                    e
-                                                ));
-                                            }
-                                        }
-                                    }
-                                    Err(e) => {
-                                        sp.fail(&format!("could not parse courses: {:#?}", e));
-                                    }
-                                }
-                            }
-                            Err(e) => {
-                                sp.fail(&format!("could not read file: {}", e));
-                            }
-                        },
-                        Err(e) => {
-                            sp.fail(&format!("could not download file: {}", e));
-                        }
-                    }
+        Some(Commands::Init(init_courses)) => match load_courses(&init_courses.uri) {
+            Ok(courses) => {
+                let diagnostics = validate(&courses);
+                for diagnostic in &diagnostics {
+                    println!("{}", diagnostic);
                 }
-                false => {
-                    let path = init_courses.uri.clone();
-                    match std::fs::read_to_string(&path) {
-                        Ok(json) => {
-                            let courses = course_manager::get_courses_from_json(json);
-                            match courses {
-                                Ok(courses) => match course_manager::initialize_courses(courses) {
-                                    Ok(_) => {
-                                        println!("courses initialized successfully");
-                                    }
-                                    Err(e) => {
-                                        println!("{:#?}", e);
-                                    }
-                                },
-                                Err(e) => {
-                                    println!("{:#?}", e);
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            println!("could not open file: {}", e);
-                        }
+                if has_errors(&diagnostics) && !init_courses.allow_invalid {
+                    println!(
+                        "the courses list is invalid, use --allow-invalid to initialize it anyway"
+                    );
+                    return;
+                }
+                match manager.initialize_courses(courses) {
+                    Ok(backup) => {
+                        println!("courses initialized successfully");
+                        print_init_backup(backup);
+                    }
+                    Err(e) => {
+                        println!("could not initialize courses: {:#?}", e);
                     }
                 }
             }
-        }
-        Some(Commands::List(list_courses)) => match requires_init() {
+            Err(e) => {
+                println!("{}", e);
+            }
+        },
+        Some(Commands::List(list_courses)) => match manager.requires_init() {
             Ok(requires_init) => {
                 if requires_init {
                     println!("please init the courses list first");
                 } else {
                     let status = &list_courses.status;
-                    let courses = course_manager::get_courses(to_course_statuses(status));
+                    let courses = manager.get_courses(to_course_statuses(status));
                     match courses {
                         Ok(courses) => match list_courses.print_format {
                             PrintFormat::Json => {
@@ -101,6 +110,12 @@ fn main() {
                                             Some(status) => to_str(status),
                                             None => "N/A",
                                         },
+                                        grade: describe_grade(course.approval.as_ref()),
+                                        term: course
+                                            .approval
+                                            .as_ref()
+                                            .and_then(|a| a.term.clone())
+                                            .unwrap_or_default(),
                                     })
                                     .collect();
                                 if courses.is_empty() {
@@ -127,18 +142,43 @@ fn main() {
                 println!("{:#?}", e);
             }
         },
-        Some(Commands::Approve(list_courses)) => match requires_init() {
+        Some(Commands::Approve(list_courses)) => match manager.requires_init() {
             Ok(requires_init) => {
                 if requires_init {
                     println!("please init the courses list first");
                 }
-                match approve_courses(
-                    &list_courses.courses,
-                    list_courses.recursive,
-                    list_courses.force,
-                ) {
-                    Ok(_) => {
-                        println!("courses approved successfully");
+                let result = if list_courses.dry_run {
+                    manager.plan_approval(
+                        &list_courses.courses,
+                        list_courses.recursive,
+                        list_courses.force,
+                        list_courses.partial,
+                    )
+                } else {
+                    let details = ApprovalDetails {
+                        grade: list_courses.grade.clone(),
+                        term: list_courses.term.clone(),
+                        ..Default::default()
+                    };
+                    manager.approve_batch(
+                        &list_courses.courses,
+                        list_courses.recursive,
+                        list_courses.force,
+                        list_courses.partial,
+                        &details,
+                    )
+                };
+                match result {
+                    Ok(report) => print_batch_report(&report, list_courses.dry_run),
+                    Err(Error::InvalidBatch(issues)) => {
+                        if list_courses.dry_run {
+                            println!("no courses would be approved:");
+                        } else {
+                            println!("no courses were approved:");
+                        }
+                        for issue in issues {
+                            println!("  {}", issue);
+                        }
                     }
                     Err(e) => {
                         println!("{:#?}", e);
@@ -149,12 +189,30 @@ fn main() {
                 println!("{:#?}", e);
             }
         },
-        Some(Commands::Reject(list_courses)) => match requires_init() {
+        Some(Commands::Reject(list_courses)) => match manager.requires_init() {
             Ok(requires_init) => {
                 if requires_init {
                     println!("please init the courses list first");
                 }
-                match reject_courses(
+                if list_courses.dry_run {
+                    match manager.plan_rejection(
+                        &list_courses.courses,
+                        list_courses.cascade,
+                        list_courses.force,
+                    ) {
+                        Ok(rejected) if rejected.is_empty() => {
+                            println!("no courses would be rejected");
+                        }
+                        Ok(rejected) => {
+                            println!("would reject {}", rejected.join(", "));
+                        }
+                        Err(e) => {
+                            println!("{:#?}", e);
+                        }
+                    }
+                    return;
+                }
+                match manager.reject_courses(
                     &list_courses.courses,
                     list_courses.cascade,
                     list_courses.force,
@@ -171,9 +229,701 @@ fn main() {
                 println!("{:#?}", e);
             }
         },
+        Some(Commands::Migrate(migrate)) => match manager.migrate(migrate.dry_run) {
+            Ok(report) => {
+                if report.is_up_to_date() {
+                    println!("the courses data is up to date (version {})", report.to);
+                } else {
+                    println!(
+                        "{} the courses data from version {} to {}:",
+                        if report.dry_run {
+                            "would migrate"
+                        } else {
+                            "migrated"
+                        },
+                        report.from,
+                        report.to
+                    );
+                    for step in report.steps {
+                        println!("  {} -> {}: {}", step.from, step.to, step.description);
+                        for change in step.changes {
+                            println!("    - {}", change);
+                        }
+                    }
+                }
+            }
+            Err(e) => {
+                println!("{:#?}", e);
+            }
+        },
+        Some(Commands::Undo) => match manager.undo() {
+            Ok(Some(entry)) => {
+                println!("reverted: {}", entry.command);
+            }
+            Ok(None) => {
+                println!("nothing to undo");
+            }
+            Err(e) => {
+                println!("{:#?}", e);
+            }
+        },
+        Some(Commands::Redo) => match manager.redo() {
+            Ok(Some(entry)) => {
+                println!("applied again: {}", entry.command);
+            }
+            Ok(None) => {
+                println!("nothing to redo");
+            }
+            Err(e) => {
+                println!("{:#?}", e);
+            }
+        },
+        Some(Commands::History(history)) => match manager.history() {
+            Ok(journal) => {
+                if journal.entries.is_empty() {
+                    println!("no history found");
+                } else {
+                    let rows: Vec<HistoryTable> = journal
+                        .entries
+                        .iter()
+                        .enumerate()
+                        .map(|(i, entry)| HistoryTable {
+                            index: i + 1,
+                            date: format_timestamp(entry.timestamp),
+                            command: entry.command.clone(),
+                            added: entry.added.join(", "),
+                            removed: entry.removed.join(", "),
+                            state: if journal.is_undone(i) {
+                                "undone"
+                            } else {
+                                "applied"
+                            },
+                        })
+                        .collect();
+                    let mut table = Table::new(&rows);
+                    let table = to_table_style(&mut table, history.table_format);
+                    println!("{}", table);
+                }
+            }
+            Err(e) => {
+                println!("{:#?}", e);
+            }
+        },
+        Some(Commands::Update(update)) => {
+            let equivalences = match &update.equivalences {
+                Some(path) => match load_equivalences(path) {
+                    Ok(equivalences) => equivalences,
+                    Err(e) => {
+                        println!("{}", e);
+                        return;
+                    }
+                },
+                None => Equivalences::default(),
+            };
+            update_courses(
+                &manager,
+                &update.uri,
+                &equivalences,
+                update.yes,
+                update.allow_invalid,
+            );
+        }
+        Some(Commands::Equivalences(equivalences)) => match &equivalences.command {
+            EquivalencesCommands::Apply(apply) => {
+                let mapping = match load_equivalences(&apply.mapping_file) {
+                    Ok(mapping) => mapping,
+                    Err(e) => {
+                        println!("{}", e);
+                        return;
+                    }
+                };
+                match &apply.to {
+                    Some(uri) => {
+                        update_courses(&manager, uri, &mapping, apply.yes, apply.allow_invalid)
+                    }
+                    None => match manager.apply_equivalences(&mapping) {
+                        Ok(added) if added.is_empty() => {
+                            println!("no courses approved through the equivalences");
+                        }
+                        Ok(added) => {
+                            println!(
+                                "courses approved through the equivalences: {}",
+                                added.join(", ")
+                            );
+                        }
+                        Err(e) => {
+                            println!("{:#?}", e);
+                        }
+                    },
+                }
+            }
+        },
+        Some(Commands::Show(show)) => match manager.course_details(&show.codes) {
+            Ok(details) => print_details(&details, show),
+            Err(e) => {
+                println!("{:#?}", e);
+            }
+        },
+        Some(Commands::Dependents(dependents)) => match manager.dependents(&dependents.code) {
+            Ok(courses) => print_dependents(&courses, dependents),
+            Err(e) => {
+                println!("{:#?}", e);
+            }
+        },
+        Some(Commands::Why(why)) => match manager.explain(&why.code) {
+            Ok(explanation) => print_explanation(&explanation, why.print_format),
+            Err(e) => {
+                println!("{:#?}", e);
+            }
+        },
+        Some(Commands::Enroll(enroll)) => {
+            match manager.enroll_courses(&enroll.courses, enroll.force) {
+                Ok(_) => {
+                    println!("courses enrolled successfully");
+                }
+                Err(e) => {
+                    println!("{:#?}", e);
+                }
+            }
+        }
+        Some(Commands::Fail(fail)) => {
+            let grade = fail.grade.clone();
+            match manager.fail_courses(&fail.courses, grade, fail.term.clone()) {
+                Ok(_) => {
+                    println!("failed attempts recorded successfully");
+                }
+                Err(e) => {
+                    println!("{:#?}", e);
+                }
+            }
+        }
+        Some(Commands::Withdraw(withdraw)) => match manager.withdraw_courses(&withdraw.courses) {
+            Ok(_) => {
+                println!("courses withdrawn successfully");
+            }
+            Err(e) => {
+                println!("{:#?}", e);
+            }
+        },
+        Some(Commands::Plan(args)) => {
+            match manager.plan_semesters(args.max_credits, args.start_semester) {
+                Ok(plan) => print_plan(&plan, args),
+                Err(e) => {
+                    println!("{:#?}", e);
+                }
+            }
+        }
+        Some(Commands::Progress(args)) => match manager.progress() {
+            Ok(progress) => print_progress(&progress, args),
+            Err(e) => {
+                println!("{:#?}", e);
+            }
+        },
+        Some(Commands::Gpa(gpa)) => {
+            match manager.statistics(gpa.scale.into(), gpa.include_failed) {
+                Ok(statistics) => print_statistics(&statistics, gpa),
+                Err(e) => {
+                    println!("{:#?}", e);
+                }
+            }
+        }
+        Some(Commands::Diff(diff)) => print_diff(diff),
+        Some(Commands::Validate(validate)) => print_validation(validate),
+        Some(Commands::Backup(backup)) => manage_backups(&manager, backup),
+        Some(Commands::Profile(_)) => unreachable!("profiles are handled before"),
         None => {
             let mut cmd = Cli::command();
             cmd.print_help().unwrap();
         }
     }
 }
+
+fn update_courses(
+    manager: &CourseManager,
+    uri: &str,
+    equivalences: &Equivalences,
+    yes: bool,
+    allow_invalid: bool,
+) {
+    let courses = match load_courses(uri) {
+        Ok(courses) => courses,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+    let diagnostics = validate(&courses);
+    for diagnostic in &diagnostics {
+        println!("{}", diagnostic);
+    }
+    if has_errors(&diagnostics) && !allow_invalid {
+        println!("the courses list is invalid, use --allow-invalid to update to it anyway");
+        return;
+    }
+    let plan = match manager.plan_update_with(&courses, equivalences) {
+        Ok(plan) => plan,
+        Err(e) => {
+            println!("{:#?}", e);
+            return;
+        }
+    };
+    println!("approvals kept: {}", plan.kept.len());
+    if !plan.mapped.is_empty() {
+        println!(
+            "courses approved through equivalences: {}",
+            plan.mapped.join(", ")
+        );
+    }
+    if !plan.orphaned.is_empty() {
+        println!(
+            "approvals of courses that no longer exist, they will be dropped: {}",
+            plan.orphaned.join(", ")
+        );
+    }
+    if !plan.orphaned_enrollment.is_empty() {
+        println!(
+            "courses in progress or failed that no longer exist, they will be dropped: {}",
+            plan.orphaned_enrollment.join(", ")
+        );
+    }
+    if !plan.newly_blocked.is_empty() {
+        println!(
+            "courses that become blocked: {}",
+            plan.newly_blocked.join(", ")
+        );
+    }
+    if !yes && !confirm("apply the update?") {
+        println!("update cancelled");
+        return;
+    }
+    match manager.apply_update_with(courses, equivalences) {
+        Ok(report) => {
+            println!("courses updated successfully");
+            print_init_backup(report.backup);
+        }
+        Err(e) => {
+            println!("{:#?}", e);
+        }
+    }
+}
+
+fn print_diff(diff: &Diff) {
+    let old = match load_courses(&diff.old_uri) {
+        Ok(courses) => courses,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+    let new = match load_courses(&diff.new_uri) {
+        Ok(courses) => courses,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+    let result = diff_curricula(&old, &new);
+    match diff.print_format {
+        PrintFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&result).unwrap());
+        }
+        PrintFormat::Table => {
+            if result.is_empty() {
+                println!("no differences found");
+                return;
+            }
+            let mut rows: Vec<DiffTable> = Vec::new();
+            for course in &result.added {
+                rows.push(DiffTable {
+                    change: "added",
+                    code: course.code.clone(),
+                    name: course.name.clone(),
+                    details: String::new(),
+                });
+            }
+            for course in &result.removed {
+                rows.push(DiffTable {
+                    change: "removed",
+                    code: course.code.clone(),
+                    name: course.name.clone(),
+                    details: String::new(),
+                });
+            }
+            for change in &result.changed {
+                let name = new
+                    .iter()
+                    .find(|c| c.code == change.code)
+                    .map(|c| c.name.clone())
+                    .unwrap_or_default();
+                rows.push(DiffTable {
+                    change: "changed",
+                    code: change.code.clone(),
+                    name,
+                    details: change
+                        .changes
+                        .iter()
+                        .map(|c| c.to_string())
+                        .collect::<Vec<String>>()
+                        .join("\n"),
+                });
+            }
+            let mut table = Table::new(&rows);
+            let table = to_table_style(&mut table, diff.table_format);
+            println!("{}", table);
+        }
+        PrintFormat::Raw => {
+            println!("{:#?}", result);
+        }
+    }
+}
+
+fn print_validation(validation: &Validate) {
+    let courses = match load_courses(&validation.uri) {
+        Ok(courses) => courses,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+    let diagnostics: Vec<Diagnostic> = validate(&courses);
+    match validation.print_format {
+        PrintFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(&diagnostics).unwrap());
+        }
+        PrintFormat::Table => {
+            if diagnostics.is_empty() {
+                println!("no problems found");
+                return;
+            }
+            let rows: Vec<DiagnosticTable> = diagnostics
+                .iter()
+                .map(|d| DiagnosticTable {
+                    severity: d.severity.to_string(),
+                    problem: d.kind.to_string(),
+                })
+                .collect();
+            let mut table = Table::new(&rows);
+            let table = to_table_style(&mut table, validation.table_format);
+            println!("{}", table);
+        }
+        PrintFormat::Raw => {
+            println!("{:#?}", diagnostics);
+        }
+    }
+}
+
+fn print_details(details: &[CourseDetails], show: &Show) {
+    match show.print_format {
+        DetailFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(details).unwrap());
+        }
+        DetailFormat::Table => {
+            for course in details {
+                let mut table = Table::new(detail_fields(course, "\n"));
+                let table = to_table_style(&mut table, show.table_format);
+                println!("{}", table);
+            }
+        }
+        DetailFormat::Raw => {
+            println!("{:#?}", details);
+        }
+        DetailFormat::Plain => {
+            for (i, course) in details.iter().enumerate() {
+                if i > 0 {
+                    println!();
+                }
+                for row in detail_fields(course, ", ") {
+                    if row.value.is_empty() {
+                        println!("{}:", row.field);
+                    } else {
+                        println!("{}: {}", row.field, row.value);
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn print_dependents(courses: &[Dependent], dependents: &Dependents) {
+    match dependents.print_format {
+        PrintFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(courses).unwrap());
+        }
+        PrintFormat::Table => {
+            if courses.is_empty() {
+                println!("no course requires {}", dependents.code);
+                return;
+            }
+            let rows: Vec<DependentTable> = courses
+                .iter()
+                .map(|c| DependentTable {
+                    code: &c.code,
+                    name: &c.name,
+                    status: c.status.map(to_str).unwrap_or("N/A"),
+                    depth: c.depth,
+                })
+                .collect();
+            let mut table = Table::new(&rows);
+            let table = to_table_style(&mut table, dependents.table_format);
+            println!("{}", table);
+        }
+        PrintFormat::Raw => {
+            println!("{:#?}", courses);
+        }
+    }
+}
+
+fn print_plan(plan: &planner::Plan, args: &Plan) {
+    match args.print_format {
+        PrintFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(plan).unwrap());
+        }
+        PrintFormat::Table => {
+            if plan.terms.is_empty() && plan.unscheduled.is_empty() {
+                println!("there are no courses left to plan");
+                return;
+            }
+            if !plan.terms.is_empty() {
+                let rows: Vec<PlanTable> = plan
+                    .terms
+                    .iter()
+                    .flat_map(|term| {
+                        term.courses.iter().map(|c| PlanTable {
+                            term: term.number,
+                            semester: term.semester,
+                            code: &c.code,
+                            name: &c.name,
+                            credits: c.credits,
+                        })
+                    })
+                    .collect();
+                let mut table = Table::new(&rows);
+                let table = to_table_style(&mut table, args.table_format);
+                println!("{}", table);
+                println!("{} term(s) to finish", plan.terms.len());
+            }
+            if !plan.unscheduled.is_empty() {
+                println!(
+                    "can not schedule {}, their requirements can not be met, they are never offered or they exceed {} credits",
+                    plan.unscheduled.join(", "),
+                    plan.max_credits
+                );
+            }
+        }
+        PrintFormat::Raw => {
+            println!("{:#?}", plan);
+        }
+    }
+}
+
+fn print_progress(progress: &progress::Progress, args: &Progress) {
+    match args.print_format {
+        PrintFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(progress).unwrap());
+        }
+        PrintFormat::Table => {
+            // each year is followed by its semesters
+            let mut rows: Vec<ProgressTable> = Vec::new();
+            for year in &progress.years {
+                rows.push(progress_row(format!("year {}", year.year), &year.tally));
+                for period in progress.periods.iter().filter(|p| p.year == year.year) {
+                    let group = format!("  semester {}", period.semester);
+                    rows.push(progress_row(group, &period.tally));
+                }
+            }
+            rows.push(progress_row("bachelor".to_string(), &progress.bachelor));
+            if progress.licentiate.total_courses > 0 {
+                rows.push(progress_row("licentiate".to_string(), &progress.licentiate));
+            }
+            rows.push(progress_row("overall".to_string(), &progress.overall));
+            let mut table = Table::new(&rows);
+            let table = to_table_style(&mut table, args.table_format);
+            println!("{}", table);
+            println!(
+                "{} bachelor and {} licentiate course(s) remaining",
+                progress.bachelor.remaining_courses, progress.licentiate.remaining_courses
+            );
+            println!(
+                "{} course(s) available now, {} in progress",
+                progress.available_courses, progress.in_progress_courses
+            );
+        }
+        PrintFormat::Raw => {
+            println!("{:#?}", progress);
+        }
+    }
+}
+
+fn print_statistics(statistics: &Statistics, gpa: &Gpa) {
+    match gpa.print_format {
+        PrintFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(statistics).unwrap());
+        }
+        PrintFormat::Table => {
+            if statistics.periods.is_empty() {
+                println!("no courses approved or failed");
+                return;
+            }
+            let mut rows: Vec<GpaTable> = statistics
+                .periods
+                .iter()
+                .map(|p| {
+                    gpa_row(
+                        format!("year {} semester {}", p.year, p.semester),
+                        &p.average,
+                    )
+                })
+                .collect();
+            rows.push(gpa_row("overall".to_string(), &statistics.overall));
+            let mut table = Table::new(&rows);
+            let table = to_table_style(&mut table, gpa.table_format);
+            println!("{}", table);
+            println!(
+                "{} of {} credits approved",
+                statistics.overall.approved_credits, statistics.total_credits
+            );
+            let ungraded = statistics.overall.approved_credits - statistics.overall.graded_credits;
+            if ungraded > 0 {
+                println!("{} approved credits have no grade", ungraded);
+            }
+        }
+        PrintFormat::Raw => {
+            println!("{:#?}", statistics);
+        }
+    }
+}
+
+fn print_explanation(explanation: &Explanation, print_format: PrintFormat) {
+    match print_format {
+        PrintFormat::Json => {
+            println!("{}", serde_json::to_string_pretty(explanation).unwrap());
+        }
+        PrintFormat::Table => {
+            let code = &explanation.code;
+            match explanation.status {
+                CourseStatus::Approved => println!("{} is already approved", code),
+                CourseStatus::Available => println!("{} is available, it can be taken now", code),
+                CourseStatus::InProgress => println!("{} is in progress", code),
+                CourseStatus::Failed if explanation.missing.is_empty() => {
+                    println!("{} was failed, it can be taken again", code)
+                }
+                CourseStatus::Failed => println!(
+                    "{} was failed, and it is now blocked by {}",
+                    code,
+                    explanation.missing.join(", ")
+                ),
+                CourseStatus::Blocked => {
+                    println!("{} is blocked by {}", code, explanation.missing.join(", "));
+                    let mut tree = format!("{} {}\n", code, explanation.name);
+                    render_tree(&explanation.chain, "", &mut tree);
+                    print!("{}", tree);
+                    match explanation.semesters {
+                        Some(semesters) => println!(
+                            "it can be taken after {} semester(s) at the earliest",
+                            semesters
+                        ),
+                        None => println!("it can never be taken, some requirements can not be met"),
+                    }
+                }
+            }
+        }
+        PrintFormat::Raw => {
+            println!("{:#?}", explanation);
+        }
+    }
+}
+
+fn print_batch_report(report: &BatchReport, dry_run: bool) {
+    if dry_run {
+        if report.approved.is_empty() {
+            println!("no courses would be approved");
+        } else {
+            println!("would approve {}", report.approved.join(", "));
+        }
+    } else if report.issues.is_empty() {
+        println!("courses approved successfully");
+        return;
+    } else if report.approved.is_empty() {
+        println!("no courses were approved");
+    } else {
+        println!("approved {}", report.approved.join(", "));
+    }
+    if !report.issues.is_empty() {
+        println!("left out:");
+        for issue in &report.issues {
+            println!("  {}", issue);
+        }
+    }
+}
+
+fn print_init_backup(backup: Option<String>) {
+    if let Some(name) = backup {
+        println!("the previous courses data was backed up as {}", name);
+    }
+}
+
+fn manage_backups(manager: &CourseManager, backup: &Backup) {
+    let result = match &backup.command {
+        BackupCommands::List => manager.list_backups().map(|backups| {
+            if backups.is_empty() {
+                println!("no backups found");
+            } else {
+                let rows: Vec<BackupTable> = backups
+                    .iter()
+                    .map(|b| BackupTable {
+                        name: b.name.clone(),
+                        date: format_timestamp(b.created),
+                        courses: b.courses,
+                        approved: b.approved,
+                    })
+                    .collect();
+                let mut table = Table::new(&rows);
+                let table = to_table_style(&mut table, backup.table_format);
+                println!("{}", table);
+            }
+        }),
+        BackupCommands::Create => manager
+            .create_backup()
+            .map(|name| println!("backup {} created", name)),
+        BackupCommands::Restore { name } => manager.restore_backup(name).map(|previous| {
+            println!("backup {} restored", name);
+            if let Some(previous) = previous {
+                println!("the replaced courses data was backed up as {}", previous);
+            }
+        }),
+    };
+    if let Err(e) = result {
+        println!("{:#?}", e);
+    }
+}
+
+fn manage_profiles(profiles: &Profiles, command: &ProfileCommands) {
+    let result = match command {
+        ProfileCommands::Create { name } => profiles.create(name).map(|_| {
+            println!(
+                "profile {} created, initialize it with init --profile {}",
+                name, name
+            )
+        }),
+        ProfileCommands::List => profiles.active().and_then(|active| {
+            profiles.list().map(|names| {
+                for name in names {
+                    let marker = if name == active { "*" } else { " " };
+                    println!("{} {}", marker, name);
+                }
+            })
+        }),
+        ProfileCommands::Switch { name } => profiles
+            .switch(name)
+            .map(|_| println!("switched to profile {}", name)),
+        ProfileCommands::Delete { name } => profiles
+            .delete(name)
+            .map(|_| println!("profile {} deleted", name)),
+        ProfileCommands::Rename { name, new_name } => profiles
+            .rename(name, new_name)
+            .map(|_| println!("profile {} renamed to {}", name, new_name)),
+    };
+    if let Err(e) = result {
+        println!("{:#?}", e);
+    }
+}
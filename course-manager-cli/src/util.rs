@@ -1,3 +1,13 @@
+use std::io::Write;
+
+use course_manager::approvals::ApprovalRecord;
+use course_manager::courses::Course;
+use course_manager::details::{CourseDetails, RelatedCourse};
+use course_manager::equivalences::Equivalences;
+use course_manager::explain::PrerequisiteNode;
+use course_manager::progress::Tally;
+use course_manager::stats::Average;
+use spinoff::{spinners, Spinner};
 use tabled::Tabled;
 
 #[derive(Tabled)]
@@ -5,4 +15,263 @@ pub struct CourseTable<'a> {
     pub code: &'a str,
     pub name: &'a str,
     pub status: &'a str,
+    pub grade: String,
+    pub term: String,
+}
+
+#[derive(Tabled)]
+pub struct HistoryTable {
+    #[tabled(rename = "#")]
+    pub index: usize,
+    pub date: String,
+    pub command: String,
+    pub added: String,
+    pub removed: String,
+    pub state: &'static str,
+}
+
+#[derive(Tabled)]
+pub struct BackupTable {
+    pub name: String,
+    pub date: String,
+    pub courses: usize,
+    pub approved: usize,
+}
+
+#[derive(Tabled)]
+pub struct DiffTable {
+    pub change: &'static str,
+    pub code: String,
+    pub name: String,
+    pub details: String,
+}
+
+#[derive(Tabled)]
+pub struct DiagnosticTable {
+    pub severity: String,
+    pub problem: String,
+}
+
+#[derive(Tabled)]
+pub struct DependentTable<'a> {
+    pub code: &'a str,
+    pub name: &'a str,
+    pub status: &'a str,
+    pub depth: usize,
+}
+
+#[derive(Tabled)]
+pub struct GpaTable {
+    pub period: String,
+    pub courses: usize,
+    pub credits: u32,
+    pub average: String,
+}
+
+#[derive(Tabled)]
+pub struct PlanTable<'a> {
+    pub term: usize,
+    pub semester: u8,
+    pub code: &'a str,
+    pub name: &'a str,
+    pub credits: u8,
+}
+
+#[derive(Tabled)]
+pub struct ProgressTable {
+    pub group: String,
+    pub courses: String,
+    pub credits: String,
+    pub progress: String,
+}
+
+#[derive(Tabled)]
+pub struct FieldTable {
+    pub field: &'static str,
+    pub value: String,
+}
+
+/// lists related courses as `code (status)`, joined by the separator
+pub fn describe_related(courses: &[RelatedCourse], separator: &str) -> String {
+    courses
+        .iter()
+        .map(|c| match (&c.name, c.status) {
+            (Some(_), Some(status)) => format!("{} ({})", c.code, status),
+            (Some(_), None) => c.code.clone(),
+            (None, _) => format!("{} (does not exist)", c.code),
+        })
+        .collect::<Vec<String>>()
+        .join(separator)
+}
+
+/// every field of a course, in the order they are shown
+pub fn detail_fields(details: &CourseDetails, separator: &str) -> Vec<FieldTable> {
+    let course = &details.course;
+    let approval = course.approval.as_ref();
+    let field = |field: &'static str, value: String| FieldTable { field, value };
+    vec![
+        field("code", course.code.clone()),
+        field("name", course.name.clone()),
+        field(
+            "status",
+            course.status.map(|s| s.to_string()).unwrap_or_default(),
+        ),
+        field("credits", course.credits.to_string()),
+        field("year", course.year.to_string()),
+        field("semester", course.semester.to_string()),
+        field(
+            "bachelor",
+            if course.is_bachelor { "yes" } else { "no" }.to_string(),
+        ),
+        field("grade", describe_grade(course.approval.as_ref())),
+        field(
+            "term",
+            approval.and_then(|a| a.term.clone()).unwrap_or_default(),
+        ),
+        field(
+            "attempt",
+            approval.map(|a| a.attempt.to_string()).unwrap_or_default(),
+        ),
+        field(
+            "notes",
+            approval.and_then(|a| a.notes.clone()).unwrap_or_default(),
+        ),
+        field(
+            "prerequisites",
+            describe_related(&details.prerequisites, separator),
+        ),
+        field(
+            "dependents",
+            describe_related(&details.dependents, separator),
+        ),
+    ]
+}
+
+/// the grade of an approved course, empty if it was not recorded
+pub fn describe_grade(approval: Option<&ApprovalRecord>) -> String {
+    approval
+        .and_then(|a| a.grade.as_ref())
+        .map(|g| g.to_string())
+        .unwrap_or_default()
+}
+
+/// a row of the gpa table, the average is empty if no course of the period has a grade
+pub fn gpa_row(period: String, average: &Average) -> GpaTable {
+    GpaTable {
+        period,
+        courses: average.approved_courses,
+        credits: average.approved_credits,
+        average: average
+            .average
+            .map(|a| format!("{:.2}", a))
+            .unwrap_or_default(),
+    }
+}
+
+/// a row of the progress table, with a bar of the approved credits
+pub fn progress_row(group: String, tally: &Tally) -> ProgressTable {
+    ProgressTable {
+        group,
+        courses: format!("{}/{}", tally.approved_courses, tally.total_courses),
+        credits: format!("{}/{}", tally.approved_credits, tally.total_credits),
+        progress: format!(
+            "{} {:>3.0}%",
+            progress_bar(tally.percentage, 20),
+            tally.percentage
+        ),
+    }
+}
+
+/// a bar of the given width filled up to the given percentage
+pub fn progress_bar(percentage: f64, width: usize) -> String {
+    let filled = ((percentage / 100.0) * width as f64).round() as usize;
+    let filled = filled.min(width);
+    format!("{}{}", "█".repeat(filled), "░".repeat(width - filled))
+}
+
+/// renders the missing requirements of a course as a tree, one line per course
+/// the nodes are listed depth first, as in an explanation chain
+pub fn render_tree(nodes: &[PrerequisiteNode], prefix: &str, out: &mut String) {
+    // whether each node is the last of its siblings, found scanning backwards
+    let mut last = vec![false; nodes.len()];
+    let mut later: Vec<bool> = Vec::new();
+    for (i, node) in nodes.iter().enumerate().rev() {
+        later.resize(node.depth + 1, false);
+        last[i] = !later[node.depth];
+        later[node.depth] = true;
+    }
+    // whether each ancestor of the current node was the last of its siblings
+    let mut ancestors: Vec<bool> = Vec::new();
+    for (node, last) in nodes.iter().zip(last) {
+        ancestors.truncate(node.depth.saturating_sub(1));
+        let name = match &node.name {
+            Some(name) => format!("{} {} ({})", node.code, name, node.status),
+            None => format!("{} (does not exist)", node.code),
+        };
+        out.push_str(prefix);
+        for ancestor_last in &ancestors {
+            out.push_str(if *ancestor_last { "    " } else { "│   " });
+        }
+        out.push_str(if last { "└── " } else { "├── " });
+        out.push_str(&name);
+        if node.repeated {
+            out.push_str(" (see above)");
+        }
+        out.push('\n');
+        ancestors.push(last);
+    }
+}
+
+/// formats a unix timestamp as an utc date
+pub fn format_timestamp(timestamp: u64) -> String {
+    match chrono::DateTime::from_timestamp(timestamp as i64, 0) {
+        Some(date) => date.format("%Y-%m-%d %H:%M:%S UTC").to_string(),
+        None => timestamp.to_string(),
+    }
+}
+
+/// loads a courses list from an uri, which can be a local file or a https url
+pub fn load_courses(uri: &str) -> Result<Vec<Course>, String> {
+    let json = if uri.starts_with("https://") {
+        // show a downloading spinner
+        let sp = Spinner::new(spinners::Dots12, "Downloading courses list", None);
+        let text = reqwest::blocking::get(uri)
+            .map_err(|e| format!("could not download file: {}", e))
+            .and_then(|response| {
+                response
+                    .text()
+                    .map_err(|e| format!("could not read file: {}", e))
+            });
+        match text {
+            Ok(text) => {
+                sp.success("courses list downloaded");
+                text
+            }
+            Err(e) => {
+                sp.fail(&e);
+                return Err(e);
+            }
+        }
+    } else {
+        std::fs::read_to_string(uri).map_err(|e| format!("could not open file: {}", e))?
+    };
+    course_manager::get_courses_from_json(json)
+        .map_err(|e| format!("could not parse courses: {:#?}", e))
+}
+
+/// loads an equivalences table from a local file
+pub fn load_equivalences(path: &str) -> Result<Equivalences, String> {
+    let json = std::fs::read_to_string(path).map_err(|e| format!("could not open file: {}", e))?;
+    Equivalences::from_json(&json).map_err(|e| format!("could not parse equivalences: {:#?}", e))
+}
+
+/// asks the user a yes or no question, anything but yes is a no
+pub fn confirm(question: &str) -> bool {
+    print!("{} [y/N] ", question);
+    let _ = std::io::stdout().flush();
+    let mut answer = String::new();
+    if std::io::stdin().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
 }
@@ -0,0 +1,69 @@
+//! approval of a full transcript in a single call
+//! run with `cargo bench -p course-manager`
+
+use std::time::{Duration, Instant};
+
+use course_manager::courses::Course;
+use course_manager::storage::MemoryStorage;
+use course_manager::CourseManager;
+
+/// a curriculum where every course requires the previous one,
+/// so approving them in order always has the requirements met
+fn chain(count: usize) -> Vec<Course> {
+    (0..count)
+        .map(|i| Course {
+            code: format!("C{}", i),
+            name: format!("course {}", i),
+            credits: 4,
+            requirements: if i == 0 {
+                vec![]
+            } else {
+                vec![format!("C{}", i - 1)]
+            },
+            year: (i / 2 + 1).min(u8::MAX as usize) as u8,
+            semester: (i % 2 + 1) as u8,
+            is_bachelor: true,
+            status: None,
+            approval: None,
+        })
+        .collect()
+}
+
+fn time<F: FnMut()>(name: &str, runs: u32, mut f: F) {
+    let mut total = Duration::ZERO;
+    for _ in 0..runs {
+        let start = Instant::now();
+        f();
+        total += start.elapsed();
+    }
+    println!("{:<45} {:>10.2?} per run", name, total / runs);
+}
+
+fn main() {
+    for count in [1_000, 5_000, 20_000] {
+        let courses = chain(count);
+        let codes: Vec<String> = courses.iter().map(|c| c.code.clone()).collect();
+        let last = vec![codes[count - 1].clone()];
+
+        time(&format!("approve {} courses (memory)", count), 5, || {
+            let manager = CourseManager::with_storage(MemoryStorage::new());
+            manager.initialize_courses(courses.clone()).unwrap();
+            manager.approve_courses(&codes, false, false).unwrap();
+        });
+        time(
+            &format!("approve {} courses recursively (memory)", count),
+            5,
+            || {
+                let manager = CourseManager::with_storage(MemoryStorage::new());
+                manager.initialize_courses(courses.clone()).unwrap();
+                manager.approve_courses(&last, true, false).unwrap();
+            },
+        );
+        time(&format!("approve {} courses (json)", count), 5, || {
+            let dir = tempfile::tempdir().unwrap();
+            let manager = CourseManager::new(dir.path());
+            manager.initialize_courses(courses.clone()).unwrap();
+            manager.approve_courses(&codes, false, false).unwrap();
+        });
+    }
+}
@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+use crate::stats::{GradeScale, LETTERS};
+use crate::CourseManager;
+
+/// grade obtained in a course, either a number or a letter such as `A+`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum Grade {
+    Numeric(f64),
+    Letter(String),
+}
+
+impl Grade {
+    /// whether the grade fits in some scale, either a number from 0 to 100
+    /// or a letter from `F` to `A+`
+    pub fn is_valid(&self) -> bool {
+        match self {
+            Grade::Numeric(number) => (0.0..=GradeScale::Hundred.max()).contains(number),
+            Grade::Letter(letter) => LETTERS.iter().any(|(l, _)| l.eq_ignore_ascii_case(letter)),
+        }
+    }
+}
+
+/// a grade is numeric if it can be parsed as a number, otherwise it is a letter
+/// fails with `Error::UnknownGrade` if the grade does not fit in any scale
+impl FromStr for Grade {
+    type Err = Error;
+
+    fn from_str(grade: &str) -> Result<Self, Error> {
+        let grade = grade.trim();
+        let parsed = match grade.parse::<f64>() {
+            Ok(number) => Grade::Numeric(number),
+            Err(_) => Grade::Letter(grade.to_string()),
+        };
+        if parsed.is_valid() {
+            Ok(parsed)
+        } else {
+            Err(Error::UnknownGrade(grade.to_string()))
+        }
+    }
+}
+
+impl std::fmt::Display for Grade {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Grade::Numeric(number) => write!(f, "{}", number),
+            Grade::Letter(letter) => write!(f, "{}", letter),
+        }
+    }
+}
+
+/// how and when a course was approved
+/// older layouts stored only the code of the course, which is still accepted
+/// when reading, so the record is deserialized from either form
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(from = "StoredRecord")]
+pub struct ApprovalRecord {
+    pub code: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub grade: Option<Grade>,
+    /// term in which the course was taken, for example `2023-1`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub term: Option<String>,
+    /// the attempt in which the course was approved, starting at 1
+    pub attempt: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+}
+
+impl ApprovalRecord {
+    /// a record of a first attempt without any details
+    pub fn new<S: Into<String>>(code: S) -> Self {
+        ApprovalRecord {
+            code: code.into(),
+            grade: None,
+            term: None,
+            attempt: 1,
+            notes: None,
+        }
+    }
+
+    /// sets the details that are given, keeping the rest
+    pub fn apply(&mut self, details: &ApprovalDetails) {
+        if let Some(grade) = &details.grade {
+            self.grade = Some(grade.clone());
+        }
+        if let Some(term) = &details.term {
+            self.term = Some(term.clone());
+        }
+        if let Some(attempt) = details.attempt {
+            self.attempt = attempt;
+        }
+        if let Some(notes) = &details.notes {
+            self.notes = Some(notes.clone());
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredRecord {
+    Code(String),
+    Record {
+        code: String,
+        #[serde(default)]
+        grade: Option<Grade>,
+        #[serde(default)]
+        term: Option<String>,
+        #[serde(default = "first_attempt")]
+        attempt: u32,
+        #[serde(default)]
+        notes: Option<String>,
+    },
+}
+
+fn first_attempt() -> u32 {
+    1
+}
+
+impl From<StoredRecord> for ApprovalRecord {
+    fn from(record: StoredRecord) -> Self {
+        match record {
+            StoredRecord::Code(code) => ApprovalRecord::new(code),
+            StoredRecord::Record {
+                code,
+                grade,
+                term,
+                attempt,
+                notes,
+            } => ApprovalRecord {
+                code,
+                grade,
+                term,
+                attempt,
+                notes,
+            },
+        }
+    }
+}
+
+/// details given when approving courses, only the ones that are set are recorded
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ApprovalDetails {
+    pub grade: Option<Grade>,
+    pub term: Option<String>,
+    pub attempt: Option<u32>,
+    pub notes: Option<String>,
+}
+
+impl ApprovalDetails {
+    pub fn is_empty(&self) -> bool {
+        self == &ApprovalDetails::default()
+    }
+}
+
+/// the codes of the given records, in the same order
+pub(crate) fn codes(records: &[ApprovalRecord]) -> Vec<String> {
+    records.iter().map(|r| r.code.clone()).collect()
+}
+
+/// the records of the given approved codes, in their order, keeping the existing
+/// record of each code and creating a new one for the codes without a record
+pub(crate) fn merge(records: &[ApprovalRecord], approved: &[String]) -> Vec<ApprovalRecord> {
+    let by_code: HashMap<&str, &ApprovalRecord> =
+        records.iter().map(|r| (r.code.as_str(), r)).collect();
+    approved
+        .iter()
+        .map(|code| match by_code.get(code.as_str()) {
+            Some(record) => (*record).clone(),
+            None => ApprovalRecord::new(code.clone()),
+        })
+        .collect()
+}
+
+impl CourseManager {
+    /// the records of the approved courses, in the order they were approved
+    pub fn approval_records(&self) -> Result<Vec<ApprovalRecord>, Error> {
+        self.ensure_migrated()?;
+        self.storage.load_records()
+    }
+}
@@ -0,0 +1,95 @@
+use serde::{Deserialize, Serialize};
+
+use crate::approvals::ApprovalRecord;
+use crate::courses::Course;
+use crate::enrollment::Enrollment;
+use crate::error::Error;
+use crate::journal::Journal;
+use crate::{util, CourseManager};
+
+/// snapshot of the curriculum, the approved courses and their history
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Backup {
+    /// seconds since the unix epoch
+    pub created: u64,
+    pub courses: Vec<Course>,
+    pub approved: Vec<ApprovalRecord>,
+    #[serde(default)]
+    pub enrollment: Enrollment,
+    #[serde(default)]
+    pub journal: Journal,
+}
+
+/// summary of a stored backup
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct BackupInfo {
+    pub name: String,
+    /// seconds since the unix epoch
+    pub created: u64,
+    pub courses: usize,
+    pub approved: usize,
+}
+
+impl CourseManager {
+    /// saves a backup of the current data, returning its name
+    pub fn create_backup(&self) -> Result<String, Error> {
+        self.ensure_migrated()?;
+        let _lock = self.storage.lock(self.lock_timeout)?;
+        self.create_backup_locked()
+    }
+
+    pub(crate) fn create_backup_locked(&self) -> Result<String, Error> {
+        let backup = Backup {
+            created: util::unix_timestamp(),
+            courses: self.storage.load_curriculum()?,
+            approved: self.storage.load_records()?,
+            enrollment: self.storage.load_enrollment()?,
+            journal: self.storage.load_journal()?,
+        };
+        // backups made in the same second get a suffix
+        let existing = self.storage.list_backups()?;
+        let base = format!("backup-{}", backup.created);
+        let mut name = base.clone();
+        let mut suffix = 1;
+        while existing.contains(&name) {
+            name = format!("{}-{}", base, suffix);
+            suffix += 1;
+        }
+        self.storage.save_backup(&name, &backup)?;
+        Ok(name)
+    }
+
+    /// the stored backups, from the oldest to the newest
+    pub fn list_backups(&self) -> Result<Vec<BackupInfo>, Error> {
+        let mut backups = Vec::new();
+        for name in self.storage.list_backups()? {
+            let backup = self.storage.load_backup(&name)?;
+            backups.push(BackupInfo {
+                name,
+                created: backup.created,
+                courses: backup.courses.len(),
+                approved: backup.approved.len(),
+            });
+        }
+        backups.sort_by(|a, b| a.created.cmp(&b.created).then(a.name.cmp(&b.name)));
+        Ok(backups)
+    }
+
+    /// replaces the current data with the one of a backup
+    /// the current data is backed up first, the name of that backup is returned
+    pub fn restore_backup(&self, name: &str) -> Result<Option<String>, Error> {
+        self.ensure_migrated()?;
+        let _lock = self.storage.lock(self.lock_timeout)?;
+        let backup = self.storage.load_backup(name)?;
+        let previous = if self.storage.has_curriculum()? {
+            Some(self.create_backup_locked()?)
+        } else {
+            None
+        };
+        self.storage.save_curriculum(&backup.courses)?;
+        self.storage.save_records(&backup.approved)?;
+        self.storage.save_enrollment(&backup.enrollment)?;
+        self.storage.save_journal(&backup.journal)?;
+        Ok(previous)
+    }
+}
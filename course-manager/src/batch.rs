@@ -0,0 +1,190 @@
+use std::collections::HashSet;
+
+use serde::Serialize;
+
+use crate::approvals::{self, ApprovalDetails};
+use crate::error::Error;
+use crate::{approve, journal, reject, CourseManager};
+
+/// a course of a batch that can not be approved or rejected
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum BatchIssue {
+    /// the course does not exist in the curriculum
+    DoesNotExist { code: String },
+    /// the course is already approved
+    AlreadyApproved { code: String },
+    /// the course is not approved, so it can not be rejected
+    NotApproved { code: String },
+    /// some requirements of the course are neither approved nor approved earlier in the batch
+    RequirementsNotMet { code: String, missing: Vec<String> },
+}
+
+impl BatchIssue {
+    /// the course the issue is about
+    pub fn code(&self) -> &str {
+        match self {
+            BatchIssue::DoesNotExist { code }
+            | BatchIssue::AlreadyApproved { code }
+            | BatchIssue::NotApproved { code }
+            | BatchIssue::RequirementsNotMet { code, .. } => code,
+        }
+    }
+}
+
+impl std::fmt::Display for BatchIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BatchIssue::DoesNotExist { code } => write!(f, "{} does not exist", code),
+            BatchIssue::AlreadyApproved { code } => write!(f, "{} is already approved", code),
+            BatchIssue::NotApproved { code } => write!(f, "{} is not approved", code),
+            BatchIssue::RequirementsNotMet { code, missing } => {
+                write!(
+                    f,
+                    "{} is missing the requirements {}",
+                    code,
+                    missing.join(", ")
+                )
+            }
+        }
+    }
+}
+
+/// the single course errors, kept for the callers of `approve_courses` and `reject_courses`
+impl From<BatchIssue> for Error {
+    fn from(issue: BatchIssue) -> Self {
+        match issue {
+            BatchIssue::DoesNotExist { code } => Error::CourseDoesNotExist(code),
+            BatchIssue::AlreadyApproved { code } => Error::CourseAlreadyApproved(code),
+            BatchIssue::NotApproved { code } => Error::CourseNotApproved(code),
+            BatchIssue::RequirementsNotMet { code, .. } => Error::CourseRequirementsNotMet(code),
+        }
+    }
+}
+
+/// result of a batch approval
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct BatchReport {
+    /// courses that were approved
+    pub approved: Vec<String>,
+    /// courses that were left out, only on a partial batch
+    pub issues: Vec<BatchIssue>,
+}
+
+impl CourseManager {
+    /// approves a series of courses as a single transaction
+    /// every course is checked before anything is written and if any of them can not
+    /// be approved nothing is, failing with `Error::InvalidBatch` listing every problem
+    /// on a partial batch the valid courses are approved and the rest are reported instead
+    /// the given details are recorded for the given courses that end up approved,
+    /// failing with `Error::UnknownGrade` if their grade does not fit in any scale
+    pub fn approve_batch(
+        &self,
+        courses: &[String],
+        cascade: bool,
+        force: bool,
+        partial: bool,
+        details: &ApprovalDetails,
+    ) -> Result<BatchReport, Error> {
+        if let Some(grade) = details.grade.as_ref().filter(|g| !g.is_valid()) {
+            return Err(Error::UnknownGrade(grade.to_string()));
+        }
+        self.ensure_migrated()?;
+        let _lock = self.storage.lock(self.lock_timeout)?;
+        let before = self.storage.load_records()?;
+        let (approved, report) =
+            self.prepare_approval(&approvals::codes(&before), courses, cascade, force, partial)?;
+        let mut after = approvals::merge(&before, &approved);
+        let settled = self.settle_enrollment(&before, &mut after)?;
+        // the details only apply to the given courses, not to the ones approved by a cascade
+        if !details.is_empty() {
+            let given: HashSet<&str> = courses.iter().map(|c| c.as_str()).collect();
+            let left_out: HashSet<&str> = report.issues.iter().map(|i| i.code()).collect();
+            for record in after.iter_mut() {
+                let code = record.code.as_str();
+                if given.contains(code) && !left_out.contains(code) {
+                    record.apply(details);
+                }
+            }
+        }
+        self.storage.save_records(&after)?;
+        self.record(
+            journal::describe_command(
+                "approve",
+                courses,
+                &[
+                    ("--recursive", cascade),
+                    ("--force", force),
+                    ("--partial", partial),
+                ],
+            ),
+            &before,
+            &after,
+            settled,
+        )?;
+        Ok(report)
+    }
+
+    /// reports what [`approve_batch`](CourseManager::approve_batch) would do,
+    /// including the courses approved through a cascade, without writing anything
+    pub fn plan_approval(
+        &self,
+        courses: &[String],
+        cascade: bool,
+        force: bool,
+        partial: bool,
+    ) -> Result<BatchReport, Error> {
+        self.ensure_migrated()?;
+        let before = self.storage.load_approved()?;
+        let (_, report) = self.prepare_approval(&before, courses, cascade, force, partial)?;
+        Ok(report)
+    }
+
+    /// the courses that [`reject_courses`](CourseManager::reject_courses) would remove,
+    /// including the ones rejected through a cascade, without writing anything
+    pub fn plan_rejection(
+        &self,
+        courses: &[String],
+        cascade: bool,
+        force: bool,
+    ) -> Result<Vec<String>, Error> {
+        self.ensure_migrated()?;
+        let curriculum = self.storage.load_curriculum()?;
+        let before = self.storage.load_approved()?;
+        let mut approved = before.clone();
+        if let Some(issue) = reject(&curriculum, &mut approved, courses, cascade, force)
+            .into_iter()
+            .next()
+        {
+            return Err(issue.into());
+        }
+        let remaining: HashSet<&String> = approved.iter().collect();
+        Ok(before
+            .iter()
+            .filter(|c| !remaining.contains(c))
+            .cloned()
+            .collect())
+    }
+
+    /// the approved courses after approving the batch, along with its report
+    fn prepare_approval(
+        &self,
+        before: &[String],
+        courses: &[String],
+        cascade: bool,
+        force: bool,
+        partial: bool,
+    ) -> Result<(Vec<String>, BatchReport), Error> {
+        let curriculum = self.storage.load_curriculum()?;
+        let mut approved = before.to_vec();
+        let issues = approve(&curriculum, &mut approved, courses, cascade, force)?;
+        if !issues.is_empty() && !partial {
+            return Err(Error::InvalidBatch(issues));
+        }
+        let report = BatchReport {
+            approved: approved[before.len()..].to_vec(),
+            issues,
+        };
+        Ok((approved, report))
+    }
+}
@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::approvals::ApprovalRecord;
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Course {
     pub code: String,
@@ -11,6 +13,9 @@ pub struct Course {
     pub is_bachelor: bool,
     // optional status
     pub status: Option<CourseStatus>,
+    /// how the course was approved, only set on approved courses
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approval: Option<ApprovalRecord>,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Hash)]
@@ -18,6 +23,10 @@ pub enum CourseStatus {
     Blocked,
     Approved,
     Available,
+    /// being taken in the current term
+    InProgress,
+    /// not approved, and failed at least once
+    Failed,
 }
 
 pub fn to_str(status: CourseStatus) -> &'static str {
@@ -25,6 +34,8 @@ pub fn to_str(status: CourseStatus) -> &'static str {
         CourseStatus::Blocked => "Blocked",
         CourseStatus::Approved => "Approved",
         CourseStatus::Available => "Available",
+        CourseStatus::InProgress => "InProgress",
+        CourseStatus::Failed => "Failed",
     }
 }
 
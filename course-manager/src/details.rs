@@ -0,0 +1,105 @@
+use std::collections::HashSet;
+
+use serde::Serialize;
+
+use crate::courses::{Course, CourseStatus};
+use crate::error::Error;
+use crate::graph::{self, PrerequisiteGraph};
+use crate::CourseManager;
+
+/// a course along with the courses directly related to it
+#[derive(Debug, Clone, Serialize)]
+pub struct CourseDetails {
+    #[serde(flatten)]
+    pub course: Course,
+    /// the courses directly required by the course
+    pub prerequisites: Vec<RelatedCourse>,
+    /// the courses that directly require the course
+    pub dependents: Vec<RelatedCourse>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct RelatedCourse {
+    pub code: String,
+    /// none if the course does not exist in the curriculum
+    pub name: Option<String>,
+    pub status: Option<CourseStatus>,
+}
+
+/// a course that requires another one, directly or through other courses
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Dependent {
+    pub code: String,
+    pub name: String,
+    pub status: Option<CourseStatus>,
+    /// length of the shortest requirement chain to the course, 1 for a direct dependent
+    pub depth: usize,
+}
+
+impl CourseManager {
+    /// every field of the given courses, with their statuses, prerequisites and dependents
+    pub fn course_details(&self, codes: &[String]) -> Result<Vec<CourseDetails>, Error> {
+        let courses = self.get_courses(None)?;
+        let graph = PrerequisiteGraph::new(&courses);
+        let by_code = graph::by_code(&courses);
+        let related = |code: &String| match by_code.get(code.as_str()) {
+            Some(course) => RelatedCourse {
+                code: code.clone(),
+                name: Some(course.name.clone()),
+                status: course.status,
+            },
+            None => RelatedCourse {
+                code: code.clone(),
+                name: None,
+                status: None,
+            },
+        };
+        codes
+            .iter()
+            .map(|code| {
+                let course = by_code
+                    .get(code.as_str())
+                    .ok_or(Error::CourseDoesNotExist(code.to_string()))?;
+                Ok(CourseDetails {
+                    course: (*course).clone(),
+                    prerequisites: course.requirements.iter().map(related).collect(),
+                    dependents: graph.dependents(code).iter().map(related).collect(),
+                })
+            })
+            .collect()
+    }
+
+    /// every course that requires the given one, directly or through other courses,
+    /// sorted by depth
+    pub fn dependents(&self, code: &str) -> Result<Vec<Dependent>, Error> {
+        let courses = self.get_courses(None)?;
+        let graph = PrerequisiteGraph::new(&courses);
+        if !graph.contains(code) {
+            return Err(Error::CourseDoesNotExist(code.to_string()));
+        }
+        let by_code = graph::by_code(&courses);
+        let mut seen: HashSet<String> = HashSet::from([code.to_string()]);
+        let mut level: Vec<String> = vec![code.to_string()];
+        let mut dependents = Vec::new();
+        let mut depth = 0;
+        // breadth first, one level of dependents at a time
+        while !level.is_empty() {
+            depth += 1;
+            let mut next = Vec::new();
+            for dependent in level.iter().flat_map(|c| graph.dependents(c)) {
+                if seen.insert(dependent.clone()) {
+                    let course = by_code[dependent.as_str()];
+                    dependents.push(Dependent {
+                        code: dependent.clone(),
+                        name: course.name.clone(),
+                        status: course.status,
+                        depth,
+                    });
+                    next.push(dependent);
+                }
+            }
+            level = next;
+        }
+        Ok(dependents)
+    }
+}
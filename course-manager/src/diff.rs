@@ -0,0 +1,157 @@
+use serde::Serialize;
+
+use crate::courses::Course;
+use crate::graph;
+
+/// a field of a course that changed between two curricula
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "field", rename_all = "snake_case")]
+pub enum FieldChange {
+    Name {
+        old: String,
+        new: String,
+    },
+    Credits {
+        old: u8,
+        new: u8,
+    },
+    Placement {
+        old_year: u8,
+        old_semester: u8,
+        new_year: u8,
+        new_semester: u8,
+    },
+    Requirements {
+        added: Vec<String>,
+        removed: Vec<String>,
+    },
+    IsBachelor {
+        old: bool,
+        new: bool,
+    },
+}
+
+impl std::fmt::Display for FieldChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FieldChange::Name { old, new } => write!(f, "name: {} -> {}", old, new),
+            FieldChange::Credits { old, new } => write!(f, "credits: {} -> {}", old, new),
+            FieldChange::Placement {
+                old_year,
+                old_semester,
+                new_year,
+                new_semester,
+            } => write!(
+                f,
+                "placement: year {} semester {} -> year {} semester {}",
+                old_year, old_semester, new_year, new_semester
+            ),
+            FieldChange::Requirements { added, removed } => {
+                write!(f, "requirements:")?;
+                if !added.is_empty() {
+                    write!(f, " +{}", added.join(" +"))?;
+                }
+                if !removed.is_empty() {
+                    write!(f, " -{}", removed.join(" -"))?;
+                }
+                Ok(())
+            }
+            FieldChange::IsBachelor { old, new } => write!(f, "bachelor: {} -> {}", old, new),
+        }
+    }
+}
+
+/// the changes of a course present in both curricula
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CourseChange {
+    pub code: String,
+    pub changes: Vec<FieldChange>,
+}
+
+/// differences between two curricula, courses are matched by code
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CurriculumDiff {
+    pub added: Vec<Course>,
+    pub removed: Vec<Course>,
+    pub changed: Vec<CourseChange>,
+}
+
+impl CurriculumDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// compares two curricula, keeping the order of the new one for added and changed courses
+/// and the order of the old one for removed courses
+pub fn diff_curricula(old: &[Course], new: &[Course]) -> CurriculumDiff {
+    let old_by_code = graph::by_code(old);
+    let new_by_code = graph::by_code(new);
+    let mut diff = CurriculumDiff::default();
+    for course in new {
+        match old_by_code.get(course.code.as_str()) {
+            Some(previous) => {
+                let changes = diff_course(previous, course);
+                if !changes.is_empty() {
+                    diff.changed.push(CourseChange {
+                        code: course.code.clone(),
+                        changes,
+                    });
+                }
+            }
+            None => diff.added.push(course.clone()),
+        }
+    }
+    diff.removed = old
+        .iter()
+        .filter(|c| !new_by_code.contains_key(c.code.as_str()))
+        .cloned()
+        .collect();
+    diff
+}
+
+fn diff_course(old: &Course, new: &Course) -> Vec<FieldChange> {
+    let mut changes = Vec::new();
+    if old.name != new.name {
+        changes.push(FieldChange::Name {
+            old: old.name.clone(),
+            new: new.name.clone(),
+        });
+    }
+    if old.credits != new.credits {
+        changes.push(FieldChange::Credits {
+            old: old.credits,
+            new: new.credits,
+        });
+    }
+    if old.year != new.year || old.semester != new.semester {
+        changes.push(FieldChange::Placement {
+            old_year: old.year,
+            old_semester: old.semester,
+            new_year: new.year,
+            new_semester: new.semester,
+        });
+    }
+    let added: Vec<String> = new
+        .requirements
+        .iter()
+        .filter(|r| !old.requirements.contains(r))
+        .cloned()
+        .collect();
+    let removed: Vec<String> = old
+        .requirements
+        .iter()
+        .filter(|r| !new.requirements.contains(r))
+        .cloned()
+        .collect();
+    if !added.is_empty() || !removed.is_empty() {
+        changes.push(FieldChange::Requirements { added, removed });
+    }
+    if old.is_bachelor != new.is_bachelor {
+        changes.push(FieldChange::IsBachelor {
+            old: old.is_bachelor,
+            new: new.is_bachelor,
+        });
+    }
+    changes
+}
@@ -0,0 +1,220 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::approvals::{ApprovalRecord, Grade};
+use crate::courses::{Course, CourseStatus};
+use crate::error::Error;
+use crate::graph;
+use crate::CourseManager;
+
+/// how the requirements that are in progress affect the courses that require them
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AvailabilityRule {
+    /// a course is only available once all its requirements are approved
+    #[default]
+    Approved,
+    /// requirements in progress count as met, so a course can be taken
+    /// in the term right after its requirements
+    InProgress,
+}
+
+/// an attempt at a course that was not approved
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FailedAttempt {
+    pub code: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub grade: Option<Grade>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub term: Option<String>,
+}
+
+/// the courses being taken and the attempts that were failed
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Enrollment {
+    #[serde(default)]
+    pub in_progress: Vec<String>,
+    /// in the order they were recorded
+    #[serde(default)]
+    pub failed: Vec<FailedAttempt>,
+}
+
+impl Enrollment {
+    /// the failed attempts of a course
+    pub fn failures<'a>(&'a self, code: &'a str) -> impl Iterator<Item = &'a FailedAttempt> {
+        self.failed.iter().filter(move |f| f.code == code)
+    }
+}
+
+/// computes the status of the courses of a curriculum
+pub(crate) struct Statuses {
+    approved: HashSet<String>,
+    in_progress: HashSet<String>,
+    failed: HashSet<String>,
+    rule: AvailabilityRule,
+}
+
+impl Statuses {
+    pub(crate) fn new(
+        records: &[ApprovalRecord],
+        enrollment: &Enrollment,
+        rule: AvailabilityRule,
+    ) -> Self {
+        Statuses {
+            approved: records.iter().map(|r| r.code.clone()).collect(),
+            in_progress: enrollment.in_progress.iter().cloned().collect(),
+            failed: enrollment.failed.iter().map(|f| f.code.clone()).collect(),
+            rule,
+        }
+    }
+
+    /// whether every requirement of the course is met under the availability rule
+    pub(crate) fn requirements_met(&self, course: &Course) -> bool {
+        course.requirements.iter().all(|r| {
+            self.approved.contains(r)
+                || (self.rule == AvailabilityRule::InProgress && self.in_progress.contains(r))
+        })
+    }
+
+    /// approved courses come first, then the ones in progress, the failed ones
+    /// and finally the ones that can or can not be taken
+    pub(crate) fn status(&self, course: &Course) -> CourseStatus {
+        if self.approved.contains(&course.code) {
+            CourseStatus::Approved
+        } else if self.in_progress.contains(&course.code) {
+            CourseStatus::InProgress
+        } else if self.failed.contains(&course.code) {
+            CourseStatus::Failed
+        } else if self.requirements_met(course) {
+            CourseStatus::Available
+        } else {
+            CourseStatus::Blocked
+        }
+    }
+}
+
+impl CourseManager {
+    /// sets how the requirements in progress affect the availability of other courses
+    pub fn with_availability_rule(mut self, rule: AvailabilityRule) -> Self {
+        self.availability = rule;
+        self
+    }
+
+    /// the courses being taken and the failed attempts
+    pub fn enrollment(&self) -> Result<Enrollment, Error> {
+        self.ensure_migrated()?;
+        self.storage.load_enrollment()
+    }
+
+    /// marks the given courses as in progress
+    /// the courses must not be approved nor already in progress, and unless forced
+    /// their requirements must be met under the availability rule
+    /// nothing is changed if any of the courses can not be enrolled
+    pub fn enroll_courses(&self, courses: &[String], force: bool) -> Result<(), Error> {
+        self.ensure_migrated()?;
+        let _lock = self.storage.lock(self.lock_timeout)?;
+        let curriculum = self.storage.load_curriculum()?;
+        let records = self.storage.load_records()?;
+        let mut enrollment = self.storage.load_enrollment()?;
+        let statuses = Statuses::new(&records, &enrollment, self.availability);
+        let by_code = graph::by_code(&curriculum);
+        for code in courses {
+            let course = by_code
+                .get(code.as_str())
+                .ok_or(Error::CourseDoesNotExist(code.clone()))?;
+            match statuses.status(course) {
+                CourseStatus::Approved => return Err(Error::CourseAlreadyApproved(code.clone())),
+                CourseStatus::InProgress => {
+                    return Err(Error::CourseAlreadyInProgress(code.clone()))
+                }
+                _ if !force && !statuses.requirements_met(course) => {
+                    return Err(Error::CourseRequirementsNotMet(code.clone()))
+                }
+                _ => {}
+            }
+            if !enrollment.in_progress.contains(code) {
+                enrollment.in_progress.push(code.clone());
+            }
+        }
+        self.storage.save_enrollment(&enrollment)
+    }
+
+    /// records a failed attempt at each of the given courses, which stop being in progress
+    /// the courses do not need to be in progress, so past attempts can be recorded,
+    /// but they must not be approved, and the grade must fit in some scale
+    pub fn fail_courses(
+        &self,
+        courses: &[String],
+        grade: Option<Grade>,
+        term: Option<String>,
+    ) -> Result<(), Error> {
+        if let Some(grade) = grade.as_ref().filter(|g| !g.is_valid()) {
+            return Err(Error::UnknownGrade(grade.to_string()));
+        }
+        self.ensure_migrated()?;
+        let _lock = self.storage.lock(self.lock_timeout)?;
+        let curriculum = self.storage.load_curriculum()?;
+        let records = self.storage.load_records()?;
+        let mut enrollment = self.storage.load_enrollment()?;
+        let by_code = graph::by_code(&curriculum);
+        for code in courses {
+            if !by_code.contains_key(code.as_str()) {
+                return Err(Error::CourseDoesNotExist(code.clone()));
+            }
+            if records.iter().any(|r| &r.code == code) {
+                return Err(Error::CourseAlreadyApproved(code.clone()));
+            }
+            enrollment.in_progress.retain(|c| c != code);
+            enrollment.failed.push(FailedAttempt {
+                code: code.clone(),
+                grade: grade.clone(),
+                term: term.clone(),
+            });
+        }
+        self.storage.save_enrollment(&enrollment)
+    }
+
+    /// drops the given courses, which must be in progress, without recording an attempt
+    pub fn withdraw_courses(&self, courses: &[String]) -> Result<(), Error> {
+        self.ensure_migrated()?;
+        let _lock = self.storage.lock(self.lock_timeout)?;
+        let mut enrollment = self.storage.load_enrollment()?;
+        for code in courses {
+            if !enrollment.in_progress.contains(code) {
+                return Err(Error::CourseNotInProgress(code.clone()));
+            }
+            enrollment.in_progress.retain(|c| c != code);
+        }
+        self.storage.save_enrollment(&enrollment)
+    }
+
+    /// the newly approved courses stop being in progress, and their records count
+    /// the failed attempts before the approval
+    /// returns the courses that stopped being in progress, to be journaled
+    /// must be called with the storage locked, before saving the records
+    pub(crate) fn settle_enrollment(
+        &self,
+        before: &[ApprovalRecord],
+        after: &mut [ApprovalRecord],
+    ) -> Result<Vec<String>, Error> {
+        let mut enrollment = self.storage.load_enrollment()?;
+        let previous: HashSet<&str> = before.iter().map(|r| r.code.as_str()).collect();
+        let mut settled = Vec::new();
+        for record in after
+            .iter_mut()
+            .filter(|r| !previous.contains(r.code.as_str()))
+        {
+            let failures = enrollment.failures(&record.code).count() as u32;
+            record.attempt = record.attempt.max(failures + 1);
+            if enrollment.in_progress.contains(&record.code) {
+                enrollment.in_progress.retain(|c| c != &record.code);
+                settled.push(record.code.clone());
+            }
+        }
+        if !settled.is_empty() {
+            self.storage.save_enrollment(&enrollment)?;
+        }
+        Ok(settled)
+    }
+}
@@ -0,0 +1,89 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::approvals;
+use crate::error::Error;
+use crate::CourseManager;
+
+/// courses of an old curriculum that are recognized as other courses of a new one
+/// the new courses are approved only when all the old ones are approved, so a merge
+/// of two courses is written as `{"from": ["A", "B"], "to": ["X"]}` and a split as
+/// `{"from": ["A"], "to": ["X", "Y"]}`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Equivalence {
+    pub from: Vec<String>,
+    pub to: Vec<String>,
+}
+
+/// table of equivalences between two curricula, stored as a json array of [`Equivalence`]
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq)]
+#[serde(transparent)]
+pub struct Equivalences {
+    pub entries: Vec<Equivalence>,
+}
+
+impl Equivalences {
+    pub fn from_json(json: &str) -> Result<Self, Error> {
+        serde_json::from_str(json).map_err(Error::JsonDeserialization)
+    }
+
+    /// the equivalences whose old courses are all approved
+    pub fn applicable<'a>(
+        &'a self,
+        approved: &'a [String],
+    ) -> impl Iterator<Item = &'a Equivalence> {
+        self.entries
+            .iter()
+            .filter(move |e| !e.from.is_empty() && e.from.iter().all(|c| approved.contains(c)))
+    }
+
+    /// the approved courses along with the courses approved through the equivalences
+    pub fn apply(&self, approved: &[String]) -> Vec<String> {
+        let mut result = approved.to_vec();
+        for equivalence in self.applicable(approved) {
+            for code in &equivalence.to {
+                if !result.contains(code) {
+                    result.push(code.clone());
+                }
+            }
+        }
+        result
+    }
+
+    /// the old courses consumed by the applicable equivalences
+    pub(crate) fn consumed(&self, approved: &[String]) -> HashSet<String> {
+        self.applicable(approved)
+            .flat_map(|e| e.from.iter().cloned())
+            .collect()
+    }
+}
+
+impl CourseManager {
+    /// approves the courses of the current curriculum that are equivalent to approved courses,
+    /// returning the newly approved courses
+    /// the requirements of the new courses are not checked, as the equivalence recognizes them
+    /// and like any other approval they stop being in progress
+    pub fn apply_equivalences(&self, equivalences: &Equivalences) -> Result<Vec<String>, Error> {
+        self.ensure_migrated()?;
+        let _lock = self.storage.lock(self.lock_timeout)?;
+        let courses = self.storage.load_curriculum()?;
+        let before = self.storage.load_records()?;
+        let mut approved = approvals::codes(&before);
+        let mut added = Vec::new();
+        for code in equivalences.apply(&approvals::codes(&before)) {
+            if !approved.contains(&code) && courses.iter().any(|c| c.code == code) {
+                approved.push(code.clone());
+                added.push(code);
+            }
+        }
+        if added.is_empty() {
+            return Ok(added);
+        }
+        let mut after = approvals::merge(&before, &approved);
+        let settled = self.settle_enrollment(&before, &mut after)?;
+        self.storage.save_records(&after)?;
+        self.record("equivalences apply".to_string(), &before, &after, settled)?;
+        Ok(added)
+    }
+}
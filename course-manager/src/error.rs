@@ -6,11 +6,41 @@ pub enum Error {
     CouldNotCreatePath(PathBuf, std::io::Error),
     CouldNotCreateFile(PathBuf, std::io::Error),
     CouldNotOpenFile(PathBuf, std::io::Error),
-    CouldNotParseConfig(figment::error::Error),
+    CouldNotParseConfig(Box<figment::error::Error>),
     JsonSerialization(serde_json::Error),
     JsonDeserialization(serde_json::Error),
     CourseAlreadyApproved(String),
     CourseDoesNotExist(String),
     CourseNotApproved(String),
     CourseRequirementsNotMet(String),
+    CourseAlreadyInProgress(String),
+    CourseNotInProgress(String),
+    NotInitialized,
+    StorageLocked(PathBuf),
+    CouldNotLock(PathBuf, std::io::Error),
+    UnsupportedSchemaVersion(u32),
+    ProfileAlreadyExists(String),
+    ProfileDoesNotExist(String),
+    ProtectedProfile(String),
+    InvalidProfileName(String),
+    BackupDoesNotExist(String),
+    /// the courses require each other in a cycle, listed in requirement order
+    PrerequisiteCycle(Vec<String>),
+    /// none of the courses of a batch was approved because of these problems
+    InvalidBatch(Vec<crate::batch::BatchIssue>),
+    /// the course with the given code has a grade that is not in the grade scale
+    InvalidGrade(String, String),
+    /// a grade that is neither a number from 0 to 100 nor a known letter
+    UnknownGrade(String),
+    /// a semester of the year outside of 1 and `validation::MAX_SEMESTER`
+    SemesterOutOfRange(u8),
+    #[cfg(feature = "sqlite")]
+    Sqlite(rusqlite::Error),
+}
+
+#[cfg(feature = "sqlite")]
+impl From<rusqlite::Error> for Error {
+    fn from(e: rusqlite::Error) -> Self {
+        Error::Sqlite(e)
+    }
 }
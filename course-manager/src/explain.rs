@@ -0,0 +1,203 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::Serialize;
+
+use crate::courses::{Course, CourseStatus};
+use crate::enrollment::AvailabilityRule;
+use crate::error::Error;
+use crate::graph::{self, PrerequisiteGraph};
+use crate::CourseManager;
+
+/// why a course can or can not be taken
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Explanation {
+    pub code: String,
+    pub name: String,
+    /// the status of the course, the requirements may be missing even if
+    /// it is in progress or failed when they were forced
+    pub status: CourseStatus,
+    /// requirements of the course that are not met
+    pub missing: Vec<String>,
+    /// the missing requirements along with their own missing requirements,
+    /// down to the courses that are available, as a tree listed depth first:
+    /// every course is followed by its own missing requirements
+    pub chain: Vec<PrerequisiteNode>,
+    /// minimum number of semesters until the course can be taken, taking one
+    /// semester for each course of the longest chain of missing requirements
+    /// none if the course can never be taken, because a requirement does not exist
+    /// or the requirements form a cycle
+    pub semesters: Option<usize>,
+}
+
+/// a missing requirement in the chain of an [`Explanation`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PrerequisiteNode {
+    pub code: String,
+    /// none if the course does not exist in the curriculum
+    pub name: Option<String>,
+    /// either available or blocked
+    pub status: CourseStatus,
+    /// level of the course in the tree, 1 for the requirements of the explained course
+    pub depth: usize,
+    /// the course is already shown elsewhere in the chain,
+    /// so its requirements are not repeated
+    pub repeated: bool,
+}
+
+impl CourseManager {
+    /// explains why the given course is blocked, or that it is not
+    /// with the in progress availability rule the courses in progress count as met
+    pub fn explain(&self, code: &str) -> Result<Explanation, Error> {
+        self.ensure_migrated()?;
+        let curriculum = self.storage.load_curriculum()?;
+        let approved: HashSet<String> = self.storage.load_approved()?.into_iter().collect();
+        let enrollment = self.storage.load_enrollment()?;
+        let mut met = approved.clone();
+        if self.availability == AvailabilityRule::InProgress {
+            met.extend(enrollment.in_progress.iter().cloned());
+        }
+        let mut explanation = explain(&curriculum, &met, code)?;
+        if !approved.contains(code) {
+            if enrollment.in_progress.iter().any(|c| c == code) {
+                explanation.status = CourseStatus::InProgress;
+            } else if enrollment.failures(code).next().is_some() {
+                explanation.status = CourseStatus::Failed;
+            }
+        }
+        Ok(explanation)
+    }
+}
+
+struct Context<'a> {
+    by_code: HashMap<&'a str, &'a Course>,
+    graph: PrerequisiteGraph,
+    approved: &'a HashSet<String>,
+}
+
+impl<'a> Context<'a> {
+    fn missing(&self, course: &'a Course) -> Vec<&'a String> {
+        let mut seen: HashSet<&str> = HashSet::new();
+        course
+            .requirements
+            .iter()
+            .filter(|r| !self.approved.contains(*r) && seen.insert(r.as_str()))
+            .collect()
+    }
+
+    /// the tree of missing requirements of a course, built with an explicit stack
+    /// so long requirement chains can not overflow it
+    fn chain(&self, course: &'a Course) -> Vec<PrerequisiteNode> {
+        let mut expanded: HashSet<&str> = HashSet::from([course.code.as_str()]);
+        let mut chain = Vec::new();
+        // stack of (code, depth), pushed in reverse so the requirements come out in order
+        let mut stack: Vec<(&str, usize)> = self
+            .missing(course)
+            .iter()
+            .rev()
+            .map(|r| (r.as_str(), 1))
+            .collect();
+        while let Some((code, depth)) = stack.pop() {
+            let Some(course) = self.by_code.get(code) else {
+                chain.push(PrerequisiteNode {
+                    code: code.to_string(),
+                    name: None,
+                    status: CourseStatus::Blocked,
+                    depth,
+                    repeated: false,
+                });
+                continue;
+            };
+            let missing = self.missing(course);
+            let repeated = !expanded.insert(code);
+            if !repeated {
+                stack.extend(missing.iter().rev().map(|r| (r.as_str(), depth + 1)));
+            }
+            chain.push(PrerequisiteNode {
+                code: code.to_string(),
+                name: Some(course.name.clone()),
+                status: if missing.is_empty() {
+                    CourseStatus::Available
+                } else {
+                    CourseStatus::Blocked
+                },
+                depth,
+                repeated: repeated && !missing.is_empty(),
+            });
+        }
+        chain
+    }
+
+    /// semesters needed to take a course that is not approved, including its own,
+    /// computed in a single pass over the missing requirements in requirement order
+    /// none if a requirement does not exist or the requirements form a cycle
+    fn semesters(&self, course: &'a Course) -> Option<usize> {
+        // the course and every course reachable through missing requirements
+        let mut reachable: Vec<&'a Course> = vec![course];
+        let mut seen: HashSet<&str> = HashSet::from([course.code.as_str()]);
+        let mut i = 0;
+        while i < reachable.len() {
+            for requirement in self.missing(reachable[i]) {
+                let required = self.by_code.get(requirement.as_str())?;
+                if seen.insert(requirement.as_str()) {
+                    reachable.push(required);
+                }
+            }
+            i += 1;
+        }
+        let codes: Vec<&str> = reachable.iter().map(|c| c.code.as_str()).collect();
+        // every requirement between the reachable courses is a missing one
+        let order = self.graph.topological_order(&codes).ok()?;
+        let mut semesters: HashMap<String, usize> = HashMap::new();
+        for code in order {
+            let semester = 1 + self
+                .missing(self.by_code[code.as_str()])
+                .iter()
+                .map(|r| semesters[r.as_str()])
+                .max()
+                .unwrap_or(0);
+            semesters.insert(code, semester);
+        }
+        semesters.get(&course.code).copied()
+    }
+}
+
+fn explain(
+    curriculum: &[Course],
+    approved: &HashSet<String>,
+    code: &str,
+) -> Result<Explanation, Error> {
+    let context = Context {
+        by_code: graph::by_code(curriculum),
+        graph: PrerequisiteGraph::new(curriculum),
+        approved,
+    };
+    let course = *context
+        .by_code
+        .get(code)
+        .ok_or(Error::CourseDoesNotExist(code.to_string()))?;
+    if approved.contains(code) {
+        return Ok(Explanation {
+            code: course.code.clone(),
+            name: course.name.clone(),
+            status: CourseStatus::Approved,
+            missing: Vec::new(),
+            chain: Vec::new(),
+            semesters: Some(0),
+        });
+    }
+    let missing = context.missing(course);
+    let chain = context.chain(course);
+    let semesters = context.semesters(course).map(|s| s - 1);
+    Ok(Explanation {
+        code: course.code.clone(),
+        name: course.name.clone(),
+        status: if missing.is_empty() {
+            CourseStatus::Available
+        } else {
+            CourseStatus::Blocked
+        },
+        missing: missing.into_iter().cloned().collect(),
+        chain,
+        semesters,
+    })
+}
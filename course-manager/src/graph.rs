@@ -0,0 +1,281 @@
+use std::cmp::Reverse;
+use std::collections::{hash_map::Entry, BinaryHeap, HashMap, HashSet, VecDeque};
+
+use crate::courses::Course;
+use crate::error::Error;
+
+/// the courses of a curriculum indexed by code, following the same rule as the graph:
+/// if a code is repeated only its first course is used
+pub(crate) fn by_code(courses: &[Course]) -> HashMap<&str, &Course> {
+    let mut by_code = HashMap::new();
+    for course in courses {
+        by_code.entry(course.code.as_str()).or_insert(course);
+    }
+    by_code
+}
+
+/// prerequisite graph of a curriculum, indexed by course code
+/// each course is a node with edges to the courses it requires (forward)
+/// and to the courses that require it (reverse)
+/// requirements on codes that are not in the curriculum are ignored,
+/// and if a code is repeated only its first course is used
+pub struct PrerequisiteGraph {
+    codes: Vec<String>,
+    index: HashMap<String, usize>,
+    requirements: Vec<Vec<usize>>,
+    dependents: Vec<Vec<usize>>,
+    cycles: Vec<Vec<usize>>,
+}
+
+impl PrerequisiteGraph {
+    /// builds the graph of the given courses, this is linear in the number of
+    /// courses and requirements
+    pub fn new(courses: &[Course]) -> Self {
+        let mut codes: Vec<String> = Vec::new();
+        let mut index: HashMap<String, usize> = HashMap::new();
+        let mut nodes: Vec<&Course> = Vec::new();
+        for course in courses {
+            if !index.contains_key(&course.code) {
+                index.insert(course.code.clone(), codes.len());
+                codes.push(course.code.clone());
+                nodes.push(course);
+            }
+        }
+        let mut requirements: Vec<Vec<usize>> = vec![Vec::new(); nodes.len()];
+        let mut dependents: Vec<Vec<usize>> = vec![Vec::new(); nodes.len()];
+        for (i, course) in nodes.iter().enumerate() {
+            for requirement in &course.requirements {
+                if let Some(&r) = index.get(requirement) {
+                    if !requirements[i].contains(&r) {
+                        requirements[i].push(r);
+                        dependents[r].push(i);
+                    }
+                }
+            }
+        }
+        let mut graph = PrerequisiteGraph {
+            codes,
+            index,
+            requirements,
+            dependents,
+            cycles: Vec::new(),
+        };
+        graph.find_cycles();
+        graph
+    }
+
+    /// number of courses in the graph
+    pub fn len(&self) -> usize {
+        self.codes.len()
+    }
+
+    /// whether the graph has no courses
+    pub fn is_empty(&self) -> bool {
+        self.codes.is_empty()
+    }
+
+    /// whether a course with the given code is in the graph
+    pub fn contains(&self, code: &str) -> bool {
+        self.index.contains_key(code)
+    }
+
+    /// the codes of the courses directly required by the given course
+    pub fn requirements(&self, code: &str) -> Vec<String> {
+        match self.index.get(code) {
+            Some(&i) => self.to_codes(&self.requirements[i]),
+            None => Vec::new(),
+        }
+    }
+
+    /// the codes of the courses that directly require the given course
+    pub fn dependents(&self, code: &str) -> Vec<String> {
+        match self.index.get(code) {
+            Some(&i) => self.to_codes(&self.dependents[i]),
+            None => Vec::new(),
+        }
+    }
+
+    /// all the courses required by the given courses, directly or through other courses
+    /// the given courses are only included if another given course requires them
+    pub fn required_closure<S: AsRef<str>>(&self, codes: &[S]) -> Vec<String> {
+        self.to_codes(&self.closure(codes, &self.requirements))
+    }
+
+    /// all the courses that require the given courses, directly or through other courses
+    /// the given courses are only included if another given course is required by them
+    pub fn dependent_closure<S: AsRef<str>>(&self, codes: &[S]) -> Vec<String> {
+        self.to_codes(&self.closure(codes, &self.dependents))
+    }
+
+    /// the cycles found in the graph, each one listed in requirement order:
+    /// every course requires the next one and the last one requires the first
+    /// every group of courses that depend on each other has at least one cycle reported
+    pub fn cycles(&self) -> Vec<Vec<String>> {
+        self.cycles.iter().map(|c| self.to_codes(c)).collect()
+    }
+
+    /// the shortest cycle going through the given course, if any
+    pub fn cycle_through(&self, code: &str) -> Option<Vec<String>> {
+        let start = *self.index.get(code)?;
+        // breadth first search for a path from the course back to itself
+        let mut parent: HashMap<usize, usize> = HashMap::new();
+        let mut queue: VecDeque<usize> = VecDeque::from([start]);
+        while let Some(node) = queue.pop_front() {
+            for &next in &self.requirements[node] {
+                if next == start {
+                    let mut cycle = vec![node];
+                    let mut current = node;
+                    while current != start {
+                        current = parent[&current];
+                        cycle.push(current);
+                    }
+                    cycle.reverse();
+                    return Some(self.to_codes(&cycle));
+                }
+                if let Entry::Vacant(e) = parent.entry(next) {
+                    e.insert(node);
+                    queue.push_back(next);
+                }
+            }
+        }
+        None
+    }
+
+    /// orders the given courses so that every course comes after the ones it requires,
+    /// only the requirements between the given courses are taken into account,
+    /// courses that are not in the graph are left out, and otherwise the given order is kept
+    /// fails with `Error::PrerequisiteCycle` if the given courses require each other in a cycle
+    pub fn topological_order<S: AsRef<str>>(&self, codes: &[S]) -> Result<Vec<String>, Error> {
+        let mut nodes: Vec<usize> = Vec::new();
+        let mut selected: HashSet<usize> = HashSet::new();
+        for code in codes {
+            if let Some(&i) = self.index.get(code.as_ref()) {
+                if selected.insert(i) {
+                    nodes.push(i);
+                }
+            }
+        }
+        // number of pending requirements of each selected course
+        let mut pending: HashMap<usize, usize> = nodes
+            .iter()
+            .map(|&n| {
+                let count = self.requirements[n]
+                    .iter()
+                    .filter(|r| selected.contains(r))
+                    .count();
+                (n, count)
+            })
+            .collect();
+        // courses ready to be placed, by their position in the given order
+        let position: HashMap<usize, usize> =
+            nodes.iter().enumerate().map(|(p, &n)| (n, p)).collect();
+        let mut ready: BinaryHeap<Reverse<usize>> = nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, n)| pending[n] == 0)
+            .map(|(p, _)| Reverse(p))
+            .collect();
+        let mut order: Vec<usize> = Vec::new();
+        while let Some(Reverse(p)) = ready.pop() {
+            let node = nodes[p];
+            order.push(node);
+            for dependent in &self.dependents[node] {
+                if let Some(count) = pending.get_mut(dependent) {
+                    *count -= 1;
+                    if *count == 0 {
+                        ready.push(Reverse(position[dependent]));
+                    }
+                }
+            }
+        }
+        if order.len() < nodes.len() {
+            // every course left has a pending requirement that is also left,
+            // so following them from any of those courses must end up in a cycle
+            let mut path: Vec<usize> = Vec::new();
+            let mut seen: HashMap<usize, usize> = HashMap::new();
+            let mut node = *nodes.iter().find(|n| pending[n] > 0).unwrap();
+            while !seen.contains_key(&node) {
+                seen.insert(node, path.len());
+                path.push(node);
+                node = *self.requirements[node]
+                    .iter()
+                    .find(|r| pending.get(r).is_some_and(|c| *c > 0))
+                    .unwrap();
+            }
+            return Err(Error::PrerequisiteCycle(
+                self.to_codes(&path[seen[&node]..]),
+            ));
+        }
+        Ok(self.to_codes(&order))
+    }
+
+    fn to_codes(&self, nodes: &[usize]) -> Vec<String> {
+        nodes.iter().map(|&n| self.codes[n].clone()).collect()
+    }
+
+    /// breadth first traversal from all the given codes at once,
+    /// every node is expanded at most once no matter how many paths lead to it
+    fn closure<S: AsRef<str>>(&self, codes: &[S], edges: &[Vec<usize>]) -> Vec<usize> {
+        let mut visited: HashSet<usize> = HashSet::new();
+        let mut queue: VecDeque<usize> = codes
+            .iter()
+            .filter_map(|c| self.index.get(c.as_ref()).copied())
+            .collect();
+        let mut expanded: HashSet<usize> = HashSet::new();
+        let mut result = Vec::new();
+        while let Some(node) = queue.pop_front() {
+            if !expanded.insert(node) {
+                continue;
+            }
+            for &next in &edges[node] {
+                if visited.insert(next) {
+                    result.push(next);
+                    queue.push_back(next);
+                }
+            }
+        }
+        result
+    }
+
+    /// iterative depth first search, so long requirement chains can not overflow the stack
+    fn find_cycles(&mut self) {
+        // 0 = not visited, 1 = in the current path, 2 = done
+        let mut state = vec![0u8; self.codes.len()];
+        let mut reported: HashSet<Vec<usize>> = HashSet::new();
+        for start in 0..self.codes.len() {
+            if state[start] != 0 {
+                continue;
+            }
+            // stack of (course, next requirement to visit)
+            let mut stack: Vec<(usize, usize)> = vec![(start, 0)];
+            state[start] = 1;
+            while let Some((node, next)) = stack.last_mut() {
+                let node = *node;
+                let Some(&requirement) = self.requirements[node].get(*next) else {
+                    state[node] = 2;
+                    stack.pop();
+                    continue;
+                };
+                *next += 1;
+                match state[requirement] {
+                    0 => {
+                        state[requirement] = 1;
+                        stack.push((requirement, 0));
+                    }
+                    1 => {
+                        let position = stack.iter().position(|(n, _)| *n == requirement);
+                        let mut cycle: Vec<usize> =
+                            stack[position.unwrap()..].iter().map(|(n, _)| *n).collect();
+                        // rotate so the same cycle is always reported the same way
+                        let min = (0..cycle.len()).min_by_key(|i| cycle[*i]).unwrap();
+                        cycle.rotate_left(min);
+                        if reported.insert(cycle.clone()) {
+                            self.cycles.push(cycle);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,218 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::approvals::ApprovalRecord;
+use crate::error::Error;
+use crate::{util, CourseManager};
+
+/// a mutation of the approved courses
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct JournalEntry {
+    /// seconds since the unix epoch
+    pub timestamp: u64,
+    /// description of the command that made the change, for example `approve A B`
+    pub command: String,
+    /// courses that were approved
+    pub added: Vec<String>,
+    /// courses that were rejected
+    pub removed: Vec<String>,
+    /// the records of the approved and rejected courses, so they are restored
+    /// when the entry is undone or redone, along with the new records of the
+    /// courses that stayed approved but had their details changed
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub records: Vec<ApprovalRecord>,
+    /// the records of the courses that had their details changed, as they were before
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub previous: Vec<ApprovalRecord>,
+    /// courses that stopped being in progress because they were approved,
+    /// they are in progress again when the entry is undone
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub settled: Vec<String>,
+}
+
+/// the history of mutations of the approved courses
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct Journal {
+    pub entries: Vec<JournalEntry>,
+    /// number of entries that are currently applied,
+    /// the entries after it have been undone and can be redone
+    pub position: usize,
+}
+
+impl Journal {
+    /// records a new entry, discarding the entries that were undone
+    pub fn push(&mut self, entry: JournalEntry) {
+        self.entries.truncate(self.position);
+        self.entries.push(entry);
+        self.position = self.entries.len();
+    }
+
+    /// whether the entry at the given index has been undone
+    pub fn is_undone(&self, index: usize) -> bool {
+        index >= self.position
+    }
+}
+
+/// description of an approval or rejection, mimicking the cli arguments
+/// only the flags that are set are included
+pub(crate) fn describe_command(name: &str, courses: &[String], flags: &[(&str, bool)]) -> String {
+    let mut command = name.to_string();
+    for (flag, set) in flags {
+        if *set {
+            command.push(' ');
+            command.push_str(flag);
+        }
+    }
+    for course in courses {
+        command.push(' ');
+        command.push_str(course);
+    }
+    command
+}
+
+impl CourseManager {
+    /// the journal of mutations of the approved courses
+    pub fn history(&self) -> Result<Journal, Error> {
+        self.ensure_migrated()?;
+        self.storage.load_journal()
+    }
+
+    /// records the difference between the approved courses before and after a command,
+    /// including the records that changed, along with the courses it took out of progress
+    /// nothing is recorded if there is no difference
+    pub(crate) fn record(
+        &self,
+        command: String,
+        before: &[ApprovalRecord],
+        after: &[ApprovalRecord],
+        settled: Vec<String>,
+    ) -> Result<(), Error> {
+        let before_set: HashSet<&str> = before.iter().map(|r| r.code.as_str()).collect();
+        let after_set: HashSet<&str> = after.iter().map(|r| r.code.as_str()).collect();
+        let added: Vec<&ApprovalRecord> = after
+            .iter()
+            .filter(|r| !before_set.contains(r.code.as_str()))
+            .collect();
+        let removed: Vec<&ApprovalRecord> = before
+            .iter()
+            .filter(|r| !after_set.contains(r.code.as_str()))
+            .collect();
+        let mut previous: Vec<ApprovalRecord> = Vec::new();
+        let mut changed: Vec<&ApprovalRecord> = Vec::new();
+        for record in after {
+            let old = before.iter().find(|r| r.code == record.code);
+            if let Some(old) = old.filter(|old| *old != record) {
+                previous.push(old.clone());
+                changed.push(record);
+            }
+        }
+        if added.is_empty() && removed.is_empty() && changed.is_empty() {
+            return Ok(());
+        }
+        let mut journal = self.storage.load_journal()?;
+        journal.push(JournalEntry {
+            timestamp: util::unix_timestamp(),
+            command,
+            added: added.iter().map(|r| r.code.clone()).collect(),
+            removed: removed.iter().map(|r| r.code.clone()).collect(),
+            records: added
+                .into_iter()
+                .chain(removed)
+                .chain(changed)
+                .cloned()
+                .collect(),
+            previous,
+            settled,
+        });
+        self.storage.save_journal(&journal)
+    }
+
+    /// reverts the last applied entry of the journal, returning it
+    /// returns none if there is nothing to undo
+    pub fn undo(&self) -> Result<Option<JournalEntry>, Error> {
+        self.ensure_migrated()?;
+        let _lock = self.storage.lock(self.lock_timeout)?;
+        let mut journal = self.storage.load_journal()?;
+        if journal.position == 0 {
+            return Ok(None);
+        }
+        let entry = journal.entries[journal.position - 1].clone();
+        self.apply_change(
+            &entry.removed,
+            &entry.added,
+            &entry.records,
+            &entry.previous,
+        )?;
+        self.apply_settlement(&entry.settled, true)?;
+        journal.position -= 1;
+        self.storage.save_journal(&journal)?;
+        Ok(Some(entry))
+    }
+
+    /// applies again the first undone entry of the journal, returning it
+    /// returns none if there is nothing to redo
+    pub fn redo(&self) -> Result<Option<JournalEntry>, Error> {
+        self.ensure_migrated()?;
+        let _lock = self.storage.lock(self.lock_timeout)?;
+        let mut journal = self.storage.load_journal()?;
+        if journal.position >= journal.entries.len() {
+            return Ok(None);
+        }
+        let entry = journal.entries[journal.position].clone();
+        let changed: Vec<ApprovalRecord> = entry
+            .records
+            .iter()
+            .filter(|r| entry.previous.iter().any(|p| p.code == r.code))
+            .cloned()
+            .collect();
+        self.apply_change(&entry.added, &entry.removed, &entry.records, &changed)?;
+        self.apply_settlement(&entry.settled, false)?;
+        journal.position += 1;
+        self.storage.save_journal(&journal)?;
+        Ok(Some(entry))
+    }
+
+    /// adds and removes courses from the approved list without any check,
+    /// the added courses get their recorded details back and the courses
+    /// with a replacement get its details
+    fn apply_change(
+        &self,
+        added: &[String],
+        removed: &[String],
+        records: &[ApprovalRecord],
+        replacements: &[ApprovalRecord],
+    ) -> Result<(), Error> {
+        let mut approved = self.storage.load_records()?;
+        approved.retain(|r| !removed.contains(&r.code));
+        for record in approved.iter_mut() {
+            if let Some(replacement) = replacements.iter().find(|r| r.code == record.code) {
+                *record = replacement.clone();
+            }
+        }
+        for course in added {
+            if !approved.iter().any(|r| &r.code == course) {
+                let record = records.iter().find(|r| &r.code == course);
+                approved.push(
+                    record
+                        .cloned()
+                        .unwrap_or_else(|| ApprovalRecord::new(course.clone())),
+                );
+            }
+        }
+        self.storage.save_records(&approved)
+    }
+
+    /// puts the settled courses back in progress, or takes them out of it again
+    fn apply_settlement(&self, settled: &[String], in_progress: bool) -> Result<(), Error> {
+        if settled.is_empty() {
+            return Ok(());
+        }
+        let mut enrollment = self.storage.load_enrollment()?;
+        enrollment.in_progress.retain(|c| !settled.contains(c));
+        if in_progress {
+            enrollment.in_progress.extend(settled.iter().cloned());
+        }
+        self.storage.save_enrollment(&enrollment)
+    }
+}
@@ -1,267 +1,381 @@
-use std::collections::HashSet;
+use std::cell::Cell;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::time::Duration;
 
+use approvals::ApprovalRecord;
+use batch::BatchIssue;
 use courses::{Course, CourseStatus};
+use enrollment::{AvailabilityRule, Enrollment, Statuses};
+use error::Error;
+use graph::PrerequisiteGraph;
+use journal::Journal;
+use storage::{JsonStorage, MigrationReport, Storage};
 
+pub mod approvals;
+pub mod backup;
+pub mod batch;
 pub mod courses;
-mod error;
+pub mod details;
+pub mod diff;
+pub mod enrollment;
+pub mod equivalences;
+pub mod error;
+pub mod explain;
+pub mod graph;
+pub mod journal;
+pub mod planner;
+pub mod profiles;
+pub mod progress;
+pub mod stats;
+pub mod storage;
 #[cfg(test)]
 mod tests;
+pub mod update;
 mod util;
+pub mod validation;
+
+/// how long a mutation waits by default for another process to release the storage
+pub const DEFAULT_LOCK_TIMEOUT: Duration = Duration::from_secs(5);
 
 pub fn add(left: usize, right: usize) -> usize {
     left + right
 }
 
-fn courses_files_path() -> Result<std::path::PathBuf, error::Error> {
-    Ok(util::get_app_data_dir()?.join("courses"))
+/// handle over a single curriculum and its approvals
+/// all the data goes through the storage given on creation,
+/// so several managers can be used side by side in the same process
+pub struct CourseManager {
+    storage: Box<dyn Storage>,
+    lock_timeout: Duration,
+    availability: AvailabilityRule,
+    /// whether the storage has already been migrated to the current schema version
+    migrated: Cell<bool>,
 }
 
-/// check whethers the app requires initialization, this is usually if the app
-/// is being run for the first time, or the course data is missing
-pub fn requires_init() -> Result<bool, error::Error> {
-    // search for courses.json in the app data dir
-    // if it does not exist return true
-    // else return false
-    return Ok(!courses_files_path()?.join("courses.json").exists());
-}
+impl CourseManager {
+    /// creates a manager that stores its data as json files under the given root directory
+    /// the directory does not need to exist, it will be created on initialization
+    pub fn new<P: Into<PathBuf>>(root: P) -> Self {
+        CourseManager::with_storage(JsonStorage::new(root.into().join("courses")))
+    }
 
-/// initialize the courses list with a list of courses
-/// this is usually done when the app is run for the first time
-/// or when the course data is missing
-/// keep in mind that this will overwrite the existing course data, so an backup should be made if want to keep the old data
-pub fn initialize_courses(courses: Vec<courses::Course>) -> Result<(), error::Error> {
-    let path = courses_files_path()?;
-    if !&path.exists() {
-        std::fs::create_dir(&path)
-            .map_err(|e| error::Error::CouldNotCreatePath(path.clone(), e))?;
+    /// creates a manager that stores its data in the default app data directory
+    /// ## notes
+    /// on a debug build this will be the workspace directory
+    /// on a release build this will be in the respective app data directory
+    pub fn from_default_dir() -> Result<Self, Error> {
+        Ok(CourseManager::new(util::get_app_data_dir()?))
     }
-    let path = path.join("courses.json");
-    let json = serde_json::to_string(&courses).map_err(|e| error::Error::JsonSerialization(e))?;
-    std::fs::write(&path, json).map_err(|e| error::Error::CouldNotCreateFile(path, e))?;
-    // additionally create a approved.json whcih is a vector of strings
-    save_approved(&Vec::new())?;
-    Ok(())
-}
 
-/// gets a filtered list of courses
-/// if the filter is None, all courses are returned
-/// if the filter is Some, only courses that match the filter are returned
-pub fn get_courses(status: Option<Vec<CourseStatus>>) -> Result<Vec<Course>, error::Error> {
-    // load courses from courses.json
-    let path = courses_files_path()?.join("courses.json");
-    let json =
-        std::fs::read_to_string(&path).map_err(|e| error::Error::CouldNotOpenFile(path, e))?;
-    let mut courses: Vec<Course> =
-        serde_json::from_str(&json).map_err(|e| error::Error::JsonDeserialization(e))?;
-    match status {
-        Some(statuses) => {
-            // if all is in the filter, return all courses
-            // load approved.json
-            let approved: Vec<String> = load_approved()?;
-            let mut filtered_courses: Vec<Course> = Vec::new();
-            // keep track of filters that have been applied
-            let mut applied_filters: HashSet<CourseStatus> = HashSet::new();
-            // check for each filter if it is in the filter
-            for status in statuses {
-                if applied_filters.contains(&status) {
-                    continue;
-                }
-                match status {
-                    CourseStatus::Blocked | CourseStatus::Available => {
-                        let requires_approved = status == CourseStatus::Available;
-                        // fetch all courses that are not in approved.json
-                        for mut course in &mut courses {
-                            if !approved.contains(&course.code) {
-                                // and if that the status is the same as the filter
-                                let requires_met = requirements_met(&course, &approved);
-                                if requires_approved == requires_met {
-                                    course.status = Some(status);
-                                    filtered_courses.push(course.clone());
-                                }
-                            }
-                        }
-                    }
-                    CourseStatus::Approved => {
-                        // fetch all courses that are in approved.json
-                        for mut course in &mut courses {
-                            if approved.contains(&course.code) {
-                                course.status = Some(CourseStatus::Approved);
-                                filtered_courses.push(course.clone());
-                            }
-                        }
-                    }
-                }
-                applied_filters.insert(status);
-            }
-            Ok(filtered_courses)
+    /// creates a manager over a custom storage backend
+    pub fn with_storage<S: Storage + 'static>(storage: S) -> Self {
+        CourseManager {
+            storage: Box::new(storage),
+            lock_timeout: DEFAULT_LOCK_TIMEOUT,
+            availability: AvailabilityRule::default(),
+            migrated: Cell::new(false),
         }
-        None => {
-            let approved: Vec<String> = load_approved()?;
-            // set the status of each course
-            for course in &mut courses {
-                let requires_met = requirements_met(&course, &approved);
-                if approved.contains(&course.code) {
-                    course.status = Some(CourseStatus::Approved);
-                } else if requires_met {
-                    course.status = Some(CourseStatus::Available);
-                } else {
-                    course.status = Some(CourseStatus::Blocked);
-                }
-            }
-            return Ok(courses);
+    }
+
+    /// sets how long mutations wait for another process to release the storage
+    /// before failing with `Error::StorageLocked`
+    pub fn with_lock_timeout(mut self, timeout: Duration) -> Self {
+        self.lock_timeout = timeout;
+        self
+    }
+
+    /// the storage backend used by this manager
+    pub fn storage(&self) -> &dyn Storage {
+        self.storage.as_ref()
+    }
+
+    /// upgrades the stored data to the current schema version
+    /// on a dry run nothing is written and the report describes what would change
+    pub fn migrate(&self, dry_run: bool) -> Result<MigrationReport, Error> {
+        if dry_run {
+            return self.storage.migrate(true);
         }
+        let _lock = self.storage.lock(self.lock_timeout)?;
+        let report = self.storage.migrate(false)?;
+        self.migrated.set(true);
+        Ok(report)
     }
-}
 
-fn requirements_met(course: &Course, approved: &Vec<String>) -> bool {
-    for requirement in &course.requirements {
-        if !approved.contains(&requirement) {
-            return false;
+    /// migrates the storage the first time it is used by this manager
+    /// the storage is only locked when there is something to migrate,
+    /// so that reads never create files
+    fn ensure_migrated(&self) -> Result<(), Error> {
+        if self.migrated.get() {
+            return Ok(());
         }
+        if self.storage.migrate(true)?.is_up_to_date() {
+            self.migrated.set(true);
+        } else {
+            self.migrate(false)?;
+        }
+        Ok(())
     }
-    return true;
-}
 
-pub fn get_courses_from_json(json: String) -> Result<Vec<Course>, error::Error> {
-    let courses = serde_json::from_str(&json).map_err(|e| error::Error::JsonDeserialization(e))?;
-    return Ok(courses);
-}
+    /// check whethers the app requires initialization, this is usually if the app
+    /// is being run for the first time, or the course data is missing
+    pub fn requires_init(&self) -> Result<bool, Error> {
+        Ok(!self.storage.has_curriculum()?)
+    }
+
+    /// initialize the courses list with a list of courses
+    /// this is usually done when the app is run for the first time
+    /// or when the course data is missing
+    /// if there is existing course data it is backed up before being overwritten,
+    /// returning the name of the backup
+    pub fn initialize_courses(&self, courses: Vec<Course>) -> Result<Option<String>, Error> {
+        self.ensure_migrated()?;
+        let _lock = self.storage.lock(self.lock_timeout)?;
+        let backup = if self.storage.has_curriculum()? {
+            Some(self.create_backup_locked()?)
+        } else {
+            None
+        };
+        self.storage.save_curriculum(&courses)?;
+        // additionally reset the approved courses and their history
+        self.storage.save_records(&[])?;
+        self.storage.save_enrollment(&Enrollment::default())?;
+        self.storage.save_journal(&Journal::default())?;
+        Ok(backup)
+    }
 
-// recursively get all the courses that are required by the given course
-// along with the courses that are required by those courses and so on
-fn get_required_courses(course: &Course, courses: &Vec<Course>) -> Vec<String> {
-    let mut required_courses: Vec<String> = Vec::new();
-    for requirement in &course.requirements {
-        for c in courses {
-            if c.code == *requirement {
-                required_courses.push(c.code.clone());
-                required_courses.append(&mut get_required_courses(c, courses));
+    /// gets a filtered list of courses
+    /// if the filter is None, all courses are returned
+    /// if the filter is Some, only courses that match the filter are returned
+    pub fn get_courses(&self, status: Option<Vec<CourseStatus>>) -> Result<Vec<Course>, Error> {
+        self.ensure_migrated()?;
+        let mut courses: Vec<Course> = self.storage.load_curriculum()?;
+        // load the approved courses and attach their records
+        let records = self.storage.load_records()?;
+        let statuses = Statuses::new(
+            &records,
+            &self.storage.load_enrollment()?,
+            self.availability,
+        );
+        let mut records: HashMap<String, ApprovalRecord> =
+            records.into_iter().map(|r| (r.code.clone(), r)).collect();
+        // set the status of each course
+        for course in &mut courses {
+            course.approval = records.remove(&course.code);
+            course.status = Some(statuses.status(course));
+        }
+        match status {
+            Some(statuses) => {
+                let mut filtered_courses: Vec<Course> = Vec::new();
+                // keep track of filters that have been applied
+                let mut applied_filters: HashSet<CourseStatus> = HashSet::new();
+                // the courses of each filter are listed in the order of the filters
+                for status in statuses {
+                    if applied_filters.insert(status) {
+                        filtered_courses
+                            .extend(courses.iter().filter(|c| c.status == Some(status)).cloned());
+                    }
+                }
+                Ok(filtered_courses)
             }
+            None => Ok(courses),
         }
     }
-    return required_courses;
-}
 
-/// recursively get all courses that require the given course
-/// along with the courses that require those courses and so on
-/// this is used to get all courses that will be affected by rejecting a course
-fn get_cascade_courses(course: &str, courses: &Vec<Course>) -> Vec<String> {
-    let mut cascade_courses: Vec<String> = Vec::new();
-    for c in courses {
-        if c.requirements.contains(&course.to_string()) {
-            cascade_courses.push(c.code.clone());
-            cascade_courses.append(&mut get_cascade_courses(&c.code, courses));
+    /// approve a series of courses
+    /// if cascade is true, all the courses required by the approved courses will be approved too
+    /// the storage is locked for the whole operation
+    pub fn approve_courses(
+        &self,
+        courses: &[String],
+        cascade: bool,
+        force: bool,
+    ) -> Result<(), Error> {
+        self.ensure_migrated()?;
+        let _lock = self.storage.lock(self.lock_timeout)?;
+        let curriculum = self.storage.load_curriculum()?;
+        let before = self.storage.load_records()?;
+        let mut approved = approvals::codes(&before);
+        let issues = approve(&curriculum, &mut approved, courses, cascade, force)?;
+        if let Some(issue) = issues.into_iter().next() {
+            return Err(issue.into());
         }
+        let mut after = approvals::merge(&before, &approved);
+        let settled = self.settle_enrollment(&before, &mut after)?;
+        self.storage.save_records(&after)?;
+        self.record(
+            journal::describe_command(
+                "approve",
+                courses,
+                &[("--recursive", cascade), ("--force", force)],
+            ),
+            &before,
+            &after,
+            settled,
+        )
+    }
+
+    /// Reject a series of courses
+    /// if cascade is true, all courses that require the rejected courses will also be rejected
+    /// the storage is locked for the whole operation
+    pub fn reject_courses(
+        &self,
+        courses: &[String],
+        cascade: bool,
+        force: bool,
+    ) -> Result<(), Error> {
+        self.ensure_migrated()?;
+        let _lock = self.storage.lock(self.lock_timeout)?;
+        let curriculum = self.storage.load_curriculum()?;
+        let before = self.storage.load_records()?;
+        let mut approved = approvals::codes(&before);
+        if let Some(issue) = reject(&curriculum, &mut approved, courses, cascade, force)
+            .into_iter()
+            .next()
+        {
+            return Err(issue.into());
+        }
+        let after = approvals::merge(&before, &approved);
+        self.storage.save_records(&after)?;
+        self.record(
+            journal::describe_command(
+                "reject",
+                courses,
+                &[("--recursive", cascade), ("--force", force)],
+            ),
+            &before,
+            &after,
+            Vec::new(),
+        )
     }
-    return cascade_courses;
 }
 
-pub fn approve_courses(
-    courses: &Vec<String>,
+/// adds the given courses to the approved list, the curriculum and the approved
+/// courses are indexed once so the cost is linear in their size
+/// the batch is approved in requirement order, so a course can be approved along with
+/// its requirements no matter the order they are given in
+/// the courses that can not be approved are left out and returned as issues,
+/// sorted by the order they were given in
+fn approve(
+    curriculum: &[Course],
+    approved: &mut Vec<String>,
+    courses: &[String],
     cascade: bool,
     force: bool,
-) -> Result<(), error::Error> {
-    let mut approved: Vec<String> = load_approved()?;
-    if cascade {
-        let courses_list = get_courses(None)?;
-        // set of courses that will be approved
-        let mut accepted_courses: HashSet<String> = HashSet::new();
-        for course_code in courses {
-            // get the course by its code
-            let course = courses_list
-                .iter()
-                .find(|c| &c.code == course_code)
-                .ok_or(error::Error::CourseDoesNotExist(course_code.to_string()))?;
-            accepted_courses.insert(course.code.clone());
-            let required_courses = get_required_courses(course, &courses_list);
-            // add all the courses that are required by the given course
-            // but not already in the approved list
-            for required_course in required_courses {
-                if !approved.contains(&required_course) {
-                    accepted_courses.insert(required_course);
-                }
-            }
-        }
-        // call itself with the set of courses that will be approved
-        return approve_courses(&accepted_courses.into_iter().collect(), false, true);
+) -> Result<Vec<BatchIssue>, Error> {
+    let by_code = graph::by_code(curriculum);
+    let mut approved_set: HashSet<String> = approved.iter().cloned().collect();
+    let mut issues: Vec<BatchIssue> = Vec::new();
+    let graph = PrerequisiteGraph::new(curriculum);
+    let (known, unknown): (Vec<String>, Vec<String>) =
+        courses.iter().cloned().partition(|c| graph.contains(c));
+    issues.extend(
+        unknown
+            .into_iter()
+            .map(|code| BatchIssue::DoesNotExist { code }),
+    );
+    let (ordered, force) = if cascade {
+        // the given courses along with all the courses they require,
+        // ordered so that requirements come first
+        let mut batch = known.clone();
+        batch.extend(graph.required_closure(&known));
+        let accepted_courses: Vec<String> = graph
+            .topological_order(&batch)?
+            .into_iter()
+            // required courses that are already approved are kept as they are
+            .filter(|c| known.contains(c) || !approved_set.contains(c))
+            .collect();
+        (accepted_courses, true)
     } else {
-        for course in courses {
-            if approved.contains(&course) && !force {
-                return Err(error::Error::CourseAlreadyApproved(course.to_string()));
-            }
-            // check that the course exists
-            let courses = get_courses(None)?;
-            let course = courses
+        match graph.topological_order(&known) {
+            Ok(order) => (order, force),
+            // the requirements of the cycle can not be met,
+            // which is reported for each of its courses below
+            Err(Error::PrerequisiteCycle(_)) => (known, force),
+            Err(e) => return Err(e),
+        }
+    };
+    for code in ordered {
+        // check that the course exists
+        let Some(course) = by_code.get(code.as_str()) else {
+            issues.push(BatchIssue::DoesNotExist { code });
+            continue;
+        };
+        if approved_set.contains(&code) && !force {
+            issues.push(BatchIssue::AlreadyApproved { code });
+            continue;
+        }
+        // check that the requirements are met unless force is true
+        if !force && !requirements_met(course, &approved_set) {
+            let missing = course
+                .requirements
                 .iter()
-                .find(|c| &c.code == course)
-                .ok_or(error::Error::CourseDoesNotExist(course.to_string()))?;
-            // check that the requirements are met unless force is true
-            if !force {
-                let requirements_met = requirements_met(&course, &approved);
-                if !requirements_met {
-                    return Err(error::Error::CourseRequirementsNotMet(course.code.clone()));
-                }
-            }
-            approved.push(course.code.clone());
+                .filter(|r| !approved_set.contains(*r))
+                .cloned()
+                .collect();
+            issues.push(BatchIssue::RequirementsNotMet { code, missing });
+            continue;
+        }
+        if approved_set.insert(code.clone()) {
+            approved.push(code);
         }
     }
-    save_approved(&approved)?;
-    Ok(())
+    // reversed so that the first position of a repeated code is used
+    let position: HashMap<&str, usize> = courses
+        .iter()
+        .enumerate()
+        .rev()
+        .map(|(i, c)| (c.as_str(), i))
+        .collect();
+    issues.sort_by_key(|issue| position.get(issue.code()).copied());
+    Ok(issues)
 }
 
-/// Reject a series of courses
-/// if cascade is true, all courses that require the rejected courses will also be rejected
-pub fn reject_courses(
-    courses: &Vec<String>,
+/// removes the given courses from the approved list, returning the courses
+/// that could not be rejected as issues
+fn reject(
+    curriculum: &[Course],
+    approved: &mut Vec<String>,
+    courses: &[String],
     cascade: bool,
     force: bool,
-) -> Result<(), error::Error> {
-    let mut approved = load_approved()?;
-    if cascade {
-        let courses_list = get_courses(None)?;
-        // set of courses that will be rejected
-        let mut rejected_courses: HashSet<String> = HashSet::new();
-        for course in courses {
-            rejected_courses.insert(course.to_string());
-            // do not reject courses that are not approved
-            let cascade_courses = get_cascade_courses(course, &courses_list);
-            for cascade_course in cascade_courses {
-                if approved.contains(&cascade_course) {
-                    rejected_courses.insert(cascade_course);
-                }
+) -> Vec<BatchIssue> {
+    let approved_set: HashSet<&str> = approved.iter().map(|c| c.as_str()).collect();
+    let graph = PrerequisiteGraph::new(curriculum);
+    // set of courses that will be rejected
+    let mut rejected_courses: HashSet<String> = courses.iter().cloned().collect();
+    let mut issues: Vec<BatchIssue> = Vec::new();
+    if !force {
+        for code in courses {
+            // an approved course can still be rejected after it left the curriculum
+            if approved_set.contains(code.as_str()) {
+                continue;
+            }
+            if graph.contains(code) {
+                issues.push(BatchIssue::NotApproved { code: code.clone() });
+            } else {
+                issues.push(BatchIssue::DoesNotExist { code: code.clone() });
             }
         }
-        // call itself with the new list of courses
-        return reject_courses(&rejected_courses.into_iter().collect(), false, force);
-    } else {
-        for course in courses {
-            if !approved.contains(&course) && !force {
-                return Err(error::Error::CourseNotApproved(course.to_string()));
+    }
+    if cascade {
+        // do not reject courses that are not approved
+        for cascade_course in graph.dependent_closure(courses) {
+            if approved_set.contains(cascade_course.as_str()) {
+                rejected_courses.insert(cascade_course);
             }
         }
-        // remove the courses from the approved list
-        approved = approved
-            .into_iter()
-            .filter(|c| !courses.contains(&c))
-            .collect();
     }
-    // save the new approved.json
-    save_approved(&approved)?;
-    Ok(())
+    // remove the courses from the approved list
+    approved.retain(|c| !rejected_courses.contains(c));
+    issues
 }
 
-fn load_approved() -> Result<Vec<String>, error::Error> {
-    let path = courses_files_path()?.join("approved.json");
-    let json = std::fs::read_to_string(&path)
-        .map_err(|e| error::Error::CouldNotOpenFile(path.clone(), e))?;
-    let approved: Vec<String> =
-        serde_json::from_str(&json).map_err(|e| error::Error::JsonDeserialization(e))?;
-    Ok(approved)
+fn requirements_met(course: &Course, approved: &HashSet<String>) -> bool {
+    course.requirements.iter().all(|r| approved.contains(r))
 }
 
-fn save_approved(courses: &Vec<String>) -> Result<(), error::Error> {
-    let path = courses_files_path()?.join("approved.json");
-    let json = serde_json::to_string(&courses).map_err(|e| error::Error::JsonSerialization(e))?;
-    std::fs::write(&path, json).map_err(|e| error::Error::CouldNotCreateFile(path, e))?;
-    Ok(())
+pub fn get_courses_from_json(json: String) -> Result<Vec<Course>, Error> {
+    let courses = serde_json::from_str(&json).map_err(Error::JsonDeserialization)?;
+    Ok(courses)
 }
@@ -0,0 +1,158 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::Serialize;
+
+use crate::courses::Course;
+use crate::error::Error;
+use crate::graph::PrerequisiteGraph;
+use crate::validation::MAX_SEMESTER;
+use crate::CourseManager;
+
+/// a course placed in a term of a [`Plan`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PlannedCourse {
+    pub code: String,
+    pub name: String,
+    pub credits: u8,
+}
+
+/// the courses to take in a term
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PlannedTerm {
+    /// position of the term in the plan, starting at 1
+    pub number: usize,
+    /// semester of the year of the term, only the courses of that semester are offered
+    pub semester: u8,
+    pub courses: Vec<PlannedCourse>,
+    pub credits: u32,
+}
+
+/// a schedule of the courses that are neither approved nor in progress
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Plan {
+    pub max_credits: u32,
+    pub terms: Vec<PlannedTerm>,
+    /// courses that can never be scheduled, because a requirement does not exist,
+    /// the requirements form a cycle, the course is never offered
+    /// or it has more credits than the maximum
+    pub unscheduled: Vec<String>,
+}
+
+impl CourseManager {
+    /// schedules the remaining courses term by term, starting with a term of the given
+    /// semester and alternating between the semesters of the year
+    /// a course is only placed in the terms of its semester, after all its requirements,
+    /// and a term never has more than the given credits
+    /// the courses in progress are expected to be approved before the first term
+    /// fails with `Error::SemesterOutOfRange` if the first semester is not a semester of the year
+    pub fn plan_semesters(&self, max_credits: u32, first_semester: u8) -> Result<Plan, Error> {
+        if first_semester == 0 || first_semester > MAX_SEMESTER {
+            return Err(Error::SemesterOutOfRange(first_semester));
+        }
+        self.ensure_migrated()?;
+        let curriculum = self.storage.load_curriculum()?;
+        let mut completed: HashSet<String> = self.storage.load_approved()?.into_iter().collect();
+        completed.extend(self.storage.load_enrollment()?.in_progress);
+        Ok(plan(&curriculum, completed, max_credits, first_semester))
+    }
+}
+
+fn plan(
+    curriculum: &[Course],
+    mut completed: HashSet<String>,
+    max_credits: u32,
+    first_semester: u8,
+) -> Plan {
+    let graph = PrerequisiteGraph::new(curriculum);
+    // only the first course of a repeated code is used
+    let mut seen: HashSet<&str> = HashSet::new();
+    let mut remaining: Vec<&Course> = curriculum
+        .iter()
+        .filter(|c| seen.insert(c.code.as_str()) && !completed.contains(&c.code))
+        .collect();
+    // the courses that unlock the longest chains go first, then the earliest in the curriculum
+    let mut memo: HashMap<String, usize> = HashMap::new();
+    for course in &remaining {
+        chain_length(&graph, &course.code, &mut memo);
+    }
+    remaining.sort_by_key(|c| (std::cmp::Reverse(memo[&c.code]), c.year, c.semester));
+    let mut terms: Vec<PlannedTerm> = Vec::new();
+    let mut semester = first_semester;
+    // terms in a row without any course, once every semester was tried nothing else fits
+    let mut idle = 0;
+    while !remaining.is_empty() && idle < MAX_SEMESTER {
+        let mut courses: Vec<PlannedCourse> = Vec::new();
+        let mut credits = 0;
+        remaining.retain(|course| {
+            let fits = course.semester == semester
+                && credits + u32::from(course.credits) <= max_credits
+                && course.requirements.iter().all(|r| completed.contains(r));
+            if fits {
+                credits += u32::from(course.credits);
+                courses.push(PlannedCourse {
+                    code: course.code.clone(),
+                    name: course.name.clone(),
+                    credits: course.credits,
+                });
+            }
+            !fits
+        });
+        if courses.is_empty() {
+            idle += 1;
+        } else {
+            idle = 0;
+        }
+        // the courses of a term are completed for the next ones
+        completed.extend(courses.iter().map(|c| c.code.clone()));
+        terms.push(PlannedTerm {
+            number: terms.len() + 1,
+            semester,
+            courses,
+            credits,
+        });
+        semester = semester % MAX_SEMESTER + 1;
+    }
+    // the terms without courses at the end were only tried, the ones
+    // in between are kept since nothing can be taken in them
+    while terms.last().is_some_and(|t| t.courses.is_empty()) {
+        terms.pop();
+    }
+    Plan {
+        max_credits,
+        terms,
+        unscheduled: remaining.iter().map(|c| c.code.clone()).collect(),
+    }
+}
+
+/// number of courses in the longest chain of courses that require the given one,
+/// including itself, a cycle ends the chain
+/// iterative so long requirement chains can not overflow the stack
+fn chain_length(graph: &PrerequisiteGraph, code: &str, memo: &mut HashMap<String, usize>) {
+    let mut visiting: HashSet<String> = HashSet::new();
+    // stack of (course, whether its dependents were already pushed)
+    let mut stack: Vec<(String, bool)> = vec![(code.to_string(), false)];
+    while let Some((node, expanded)) = stack.pop() {
+        if expanded {
+            // dependents still being visited are in a cycle with the course
+            let length = 1 + graph
+                .dependents(&node)
+                .iter()
+                .filter_map(|d| memo.get(d))
+                .max()
+                .unwrap_or(&0);
+            visiting.remove(&node);
+            memo.insert(node, length);
+            continue;
+        }
+        if memo.contains_key(&node) || !visiting.insert(node.clone()) {
+            continue;
+        }
+        let dependents = graph.dependents(&node);
+        stack.push((node, true));
+        for dependent in dependents {
+            if !memo.contains_key(&dependent) && !visiting.contains(&dependent) {
+                stack.push((dependent, false));
+            }
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+use crate::storage::json::{read_json, write_json};
+use crate::{util, CourseManager};
+
+/// name of the profile that uses the data at the root directory,
+/// it always exists and can not be deleted nor renamed
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// contents of `profiles.json`
+#[derive(Serialize, Deserialize, Debug, Clone)]
+struct ProfilesConfig {
+    active: String,
+}
+
+/// named profiles, each one with its own curriculum and approvals
+/// ## notes
+/// the default profile keeps its data at the root directory (in `courses`)
+/// so existing installs keep working, the rest are stored in `profiles/<name>`
+/// and the active profile is recorded in `profiles.json`
+#[derive(Debug, Clone)]
+pub struct Profiles {
+    root: PathBuf,
+}
+
+impl Profiles {
+    /// profiles stored under the given root directory
+    pub fn new<P: Into<PathBuf>>(root: P) -> Self {
+        Profiles { root: root.into() }
+    }
+
+    /// profiles stored in the default app data directory
+    pub fn from_default_dir() -> Result<Self, Error> {
+        Ok(Profiles::new(util::get_app_data_dir()?))
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn config_path(&self) -> PathBuf {
+        self.root.join("profiles.json")
+    }
+
+    fn profiles_dir(&self) -> PathBuf {
+        self.root.join("profiles")
+    }
+
+    /// the root directory of the data of a profile
+    fn profile_root(&self, name: &str) -> PathBuf {
+        if name == DEFAULT_PROFILE {
+            self.root.clone()
+        } else {
+            self.profiles_dir().join(name)
+        }
+    }
+
+    /// whether a profile exists, never true for an invalid name
+    pub fn exists(&self, name: &str) -> bool {
+        name == DEFAULT_PROFILE || (validate_name(name).is_ok() && self.profile_root(name).is_dir())
+    }
+
+    /// fails unless the name is valid and the profile exists
+    fn ensure_exists(&self, name: &str) -> Result<(), Error> {
+        validate_name(name)?;
+        if !self.exists(name) {
+            return Err(Error::ProfileDoesNotExist(name.to_string()));
+        }
+        Ok(())
+    }
+
+    /// the names of all the profiles, starting with the default one
+    pub fn list(&self) -> Result<Vec<String>, Error> {
+        let mut names = Vec::new();
+        let dir = self.profiles_dir();
+        if dir.exists() {
+            let entries =
+                std::fs::read_dir(&dir).map_err(|e| Error::CouldNotOpenFile(dir.clone(), e))?;
+            for entry in entries {
+                let entry = entry.map_err(|e| Error::CouldNotOpenFile(dir.clone(), e))?;
+                if entry.path().is_dir() {
+                    names.push(entry.file_name().to_string_lossy().to_string());
+                }
+            }
+        }
+        names.sort();
+        names.insert(0, DEFAULT_PROFILE.to_string());
+        Ok(names)
+    }
+
+    /// the name of the active profile
+    /// if the recorded profile no longer exists the default one is used
+    pub fn active(&self) -> Result<String, Error> {
+        let path = self.config_path();
+        if !path.exists() {
+            return Ok(DEFAULT_PROFILE.to_string());
+        }
+        let config: ProfilesConfig = read_json(&path)?;
+        if self.exists(&config.active) {
+            Ok(config.active)
+        } else {
+            Ok(DEFAULT_PROFILE.to_string())
+        }
+    }
+
+    fn set_active(&self, name: &str) -> Result<(), Error> {
+        if !self.root.exists() {
+            std::fs::create_dir_all(&self.root)
+                .map_err(|e| Error::CouldNotCreatePath(self.root.clone(), e))?;
+        }
+        write_json(
+            &self.config_path(),
+            &ProfilesConfig {
+                active: name.to_string(),
+            },
+        )
+    }
+
+    /// creates an empty profile, it needs to be initialized before use
+    pub fn create(&self, name: &str) -> Result<(), Error> {
+        validate_name(name)?;
+        if self.exists(name) {
+            return Err(Error::ProfileAlreadyExists(name.to_string()));
+        }
+        let path = self.profile_root(name);
+        std::fs::create_dir_all(&path).map_err(|e| Error::CouldNotCreatePath(path, e))
+    }
+
+    /// makes the given profile the active one
+    pub fn switch(&self, name: &str) -> Result<(), Error> {
+        self.ensure_exists(name)?;
+        self.set_active(name)
+    }
+
+    /// deletes a profile along with all its data
+    /// if it was the active profile the default one becomes active
+    pub fn delete(&self, name: &str) -> Result<(), Error> {
+        if name == DEFAULT_PROFILE {
+            return Err(Error::ProtectedProfile(name.to_string()));
+        }
+        self.ensure_exists(name)?;
+        let active = self.active()?;
+        let path = self.profile_root(name);
+        std::fs::remove_dir_all(&path).map_err(|e| Error::CouldNotCreatePath(path, e))?;
+        if active == name {
+            self.set_active(DEFAULT_PROFILE)?;
+        }
+        Ok(())
+    }
+
+    /// renames a profile, keeping it active if it was
+    pub fn rename(&self, name: &str, new_name: &str) -> Result<(), Error> {
+        if name == DEFAULT_PROFILE {
+            return Err(Error::ProtectedProfile(name.to_string()));
+        }
+        self.ensure_exists(name)?;
+        validate_name(new_name)?;
+        if self.exists(new_name) {
+            return Err(Error::ProfileAlreadyExists(new_name.to_string()));
+        }
+        let active = self.active()?;
+        let new_path = self.profile_root(new_name);
+        std::fs::rename(self.profile_root(name), &new_path)
+            .map_err(|e| Error::CouldNotCreatePath(new_path, e))?;
+        if active == name {
+            self.set_active(new_name)?;
+        }
+        Ok(())
+    }
+
+    /// a manager over the data of the given profile
+    pub fn manager(&self, name: &str) -> Result<CourseManager, Error> {
+        self.ensure_exists(name)?;
+        Ok(CourseManager::new(self.profile_root(name)))
+    }
+
+    /// a manager over the data of the active profile
+    pub fn active_manager(&self) -> Result<CourseManager, Error> {
+        self.manager(&self.active()?)
+    }
+}
+
+/// profile names are used as directory names, so only a safe subset is allowed
+fn validate_name(name: &str) -> Result<(), Error> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(Error::InvalidProfileName(name.to_string()))
+    }
+}
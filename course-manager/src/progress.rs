@@ -0,0 +1,122 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::courses::{Course, CourseStatus};
+use crate::error::Error;
+use crate::CourseManager;
+
+/// approved and total courses and credits of a group of courses
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Tally {
+    pub approved_courses: usize,
+    pub total_courses: usize,
+    pub approved_credits: u32,
+    pub total_credits: u32,
+    pub remaining_courses: usize,
+    pub remaining_credits: u32,
+    /// approved credits over the total credits, from 0 to 100,
+    /// by courses if the group has no credits
+    pub percentage: f64,
+}
+
+impl Tally {
+    fn add(&mut self, course: &Course) {
+        let approved = course.status == Some(CourseStatus::Approved);
+        self.total_courses += 1;
+        self.total_credits += u32::from(course.credits);
+        if approved {
+            self.approved_courses += 1;
+            self.approved_credits += u32::from(course.credits);
+        }
+        self.remaining_courses = self.total_courses - self.approved_courses;
+        self.remaining_credits = self.total_credits - self.approved_credits;
+        self.percentage = if self.total_credits > 0 {
+            f64::from(self.approved_credits) * 100.0 / f64::from(self.total_credits)
+        } else {
+            self.approved_courses as f64 * 100.0 / self.total_courses as f64
+        };
+    }
+}
+
+/// progress in the courses of a semester of the curriculum
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PeriodProgress {
+    pub year: u8,
+    pub semester: u8,
+    #[serde(flatten)]
+    pub tally: Tally,
+}
+
+/// progress in the courses of a year of the curriculum
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct YearProgress {
+    pub year: u8,
+    #[serde(flatten)]
+    pub tally: Tally,
+}
+
+/// how far along the curriculum the approvals are
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Progress {
+    pub overall: Tally,
+    /// one entry per year and semester, in curriculum order
+    pub periods: Vec<PeriodProgress>,
+    /// one entry per year, adding up its semesters
+    pub years: Vec<YearProgress>,
+    /// the courses needed for the bachelor degree
+    pub bachelor: Tally,
+    /// the courses only needed for the licentiate degree
+    pub licentiate: Tally,
+    /// courses that can be taken now
+    pub available_courses: usize,
+    pub in_progress_courses: usize,
+}
+
+impl CourseManager {
+    /// the approved courses and credits, overall, per year, per semester and per degree
+    pub fn progress(&self) -> Result<Progress, Error> {
+        Ok(progress(&self.get_courses(None)?))
+    }
+}
+
+fn progress(courses: &[Course]) -> Progress {
+    let mut overall = Tally::default();
+    let mut periods: BTreeMap<(u8, u8), Tally> = BTreeMap::new();
+    let mut years: BTreeMap<u8, Tally> = BTreeMap::new();
+    let mut bachelor = Tally::default();
+    let mut licentiate = Tally::default();
+    for course in courses {
+        overall.add(course);
+        periods
+            .entry((course.year, course.semester))
+            .or_default()
+            .add(course);
+        years.entry(course.year).or_default().add(course);
+        if course.is_bachelor {
+            bachelor.add(course);
+        } else {
+            licentiate.add(course);
+        }
+    }
+    let count = |status: CourseStatus| courses.iter().filter(|c| c.status == Some(status)).count();
+    Progress {
+        overall,
+        periods: periods
+            .into_iter()
+            .map(|((year, semester), tally)| PeriodProgress {
+                year,
+                semester,
+                tally,
+            })
+            .collect(),
+        years: years
+            .into_iter()
+            .map(|(year, tally)| YearProgress { year, tally })
+            .collect(),
+        bachelor,
+        licentiate,
+        available_courses: count(CourseStatus::Available),
+        in_progress_courses: count(CourseStatus::InProgress),
+    }
+}
@@ -0,0 +1,231 @@
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::approvals::{ApprovalRecord, Grade};
+use crate::courses::Course;
+use crate::enrollment::Enrollment;
+use crate::error::Error;
+use crate::graph;
+use crate::CourseManager;
+
+/// scale in which the grades are expressed
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GradeScale {
+    /// numeric grades from 0 to 10
+    Ten,
+    /// numeric grades from 0 to 100
+    Hundred,
+    /// letter grades from `F` to `A+`, averaged in a 0 to 4 scale
+    Letter,
+}
+
+/// value of each letter grade in a 0 to 4 scale
+pub(crate) const LETTERS: &[(&str, f64)] = &[
+    ("A+", 4.0),
+    ("A", 4.0),
+    ("A-", 3.7),
+    ("B+", 3.3),
+    ("B", 3.0),
+    ("B-", 2.7),
+    ("C+", 2.3),
+    ("C", 2.0),
+    ("C-", 1.7),
+    ("D+", 1.3),
+    ("D", 1.0),
+    ("D-", 0.7),
+    ("F", 0.0),
+];
+
+impl GradeScale {
+    /// lowest grade of the scale, also used for the failed attempts
+    pub fn min(&self) -> f64 {
+        0.0
+    }
+
+    /// highest grade of the scale
+    pub fn max(&self) -> f64 {
+        match self {
+            GradeScale::Ten => 10.0,
+            GradeScale::Hundred => 100.0,
+            GradeScale::Letter => 4.0,
+        }
+    }
+
+    /// value of a grade in the scale, numeric grades must be within the scale
+    /// and letter grades are converted proportionally to it
+    /// none if the grade is out of the scale or is not a known letter
+    pub fn value(&self, grade: &Grade) -> Option<f64> {
+        match grade {
+            Grade::Numeric(number) => (self.min()..=self.max())
+                .contains(number)
+                .then_some(*number),
+            Grade::Letter(letter) => LETTERS
+                .iter()
+                .find(|(l, _)| l.eq_ignore_ascii_case(letter))
+                .map(|(_, value)| value / 4.0 * self.max()),
+        }
+    }
+}
+
+/// credit weighted average of a group of courses
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Average {
+    /// sum of grade × credits divided by the credits of the graded courses,
+    /// none if no course of the group has a grade
+    pub average: Option<f64>,
+    pub approved_courses: usize,
+    pub approved_credits: u32,
+    /// credits of the approved courses that have a grade
+    pub graded_credits: u32,
+    /// failed attempts taken into account, zero unless they are included
+    pub failed_attempts: u32,
+    #[serde(skip)]
+    weighted_sum: f64,
+    #[serde(skip)]
+    weight: f64,
+}
+
+impl Average {
+    fn new() -> Self {
+        Average {
+            average: None,
+            approved_courses: 0,
+            approved_credits: 0,
+            graded_credits: 0,
+            failed_attempts: 0,
+            weighted_sum: 0.0,
+            weight: 0.0,
+        }
+    }
+
+    fn add(&mut self, credits: u8, grade: Option<f64>) {
+        let credits = u32::from(credits);
+        self.approved_courses += 1;
+        self.approved_credits += credits;
+        if let Some(grade) = grade {
+            self.graded_credits += credits;
+            self.weigh(credits, grade);
+        }
+    }
+
+    /// a failed attempt weighs as much as an approval of the same course
+    fn add_failed(&mut self, credits: u8, grade: f64) {
+        self.failed_attempts += 1;
+        self.weigh(u32::from(credits), grade);
+    }
+
+    fn weigh(&mut self, credits: u32, grade: f64) {
+        self.weighted_sum += grade * f64::from(credits);
+        self.weight += f64::from(credits);
+        self.average = Some(self.weighted_sum / self.weight);
+    }
+}
+
+/// average of the courses of a semester of the curriculum
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PeriodAverage {
+    pub year: u8,
+    pub semester: u8,
+    #[serde(flatten)]
+    pub average: Average,
+}
+
+/// weighted averages of the approved courses
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Statistics {
+    pub scale: GradeScale,
+    pub include_failed: bool,
+    pub overall: Average,
+    /// one entry per year and semester with approved or failed courses, in curriculum order
+    pub periods: Vec<PeriodAverage>,
+    /// credits of the whole curriculum
+    pub total_credits: u32,
+}
+
+impl CourseManager {
+    /// the credit weighted averages of the approved courses, overall and per semester
+    /// approved courses without a grade only count towards the approved credits
+    /// if included, the recorded failed attempts count with their grade, and the ones
+    /// without a grade or only known from the attempt of an approval count as the
+    /// lowest grade of the scale
+    pub fn statistics(&self, scale: GradeScale, include_failed: bool) -> Result<Statistics, Error> {
+        self.ensure_migrated()?;
+        let curriculum = self.storage.load_curriculum()?;
+        let records = self.storage.load_records()?;
+        let enrollment = if include_failed {
+            self.storage.load_enrollment()?
+        } else {
+            Enrollment::default()
+        };
+        statistics(&curriculum, &records, &enrollment, scale, include_failed)
+    }
+}
+
+fn statistics(
+    curriculum: &[Course],
+    records: &[ApprovalRecord],
+    enrollment: &Enrollment,
+    scale: GradeScale,
+    include_failed: bool,
+) -> Result<Statistics, Error> {
+    let by_code = graph::by_code(curriculum);
+    let value = |code: &str, grade: &Option<Grade>| match grade {
+        Some(grade) => scale
+            .value(grade)
+            .map(Some)
+            .ok_or_else(|| Error::InvalidGrade(code.to_string(), grade.to_string())),
+        None => Ok(None),
+    };
+    let mut overall = Average::new();
+    let mut periods: BTreeMap<(u8, u8), Average> = BTreeMap::new();
+    for record in records {
+        // approvals of courses that are no longer in the curriculum have no credits
+        let Some(course) = by_code.get(record.code.as_str()) else {
+            continue;
+        };
+        let grade = value(&record.code, &record.grade)?;
+        overall.add(course.credits, grade);
+        periods
+            .entry((course.year, course.semester))
+            .or_insert_with(Average::new)
+            .add(course.credits, grade);
+        if include_failed {
+            // attempts before the approval that were not recorded as failed
+            let recorded = enrollment.failures(&record.code).count() as u32;
+            for _ in recorded..record.attempt.saturating_sub(1) {
+                overall.add_failed(course.credits, scale.min());
+                periods
+                    .entry((course.year, course.semester))
+                    .or_insert_with(Average::new)
+                    .add_failed(course.credits, scale.min());
+            }
+        }
+    }
+    for failure in &enrollment.failed {
+        let Some(course) = by_code.get(failure.code.as_str()) else {
+            continue;
+        };
+        let grade = value(&failure.code, &failure.grade)?.unwrap_or(scale.min());
+        overall.add_failed(course.credits, grade);
+        periods
+            .entry((course.year, course.semester))
+            .or_insert_with(Average::new)
+            .add_failed(course.credits, grade);
+    }
+    Ok(Statistics {
+        scale,
+        include_failed,
+        overall,
+        periods: periods
+            .into_iter()
+            .map(|((year, semester), average)| PeriodAverage {
+                year,
+                semester,
+                average,
+            })
+            .collect(),
+        total_credits: curriculum.iter().map(|c| u32::from(c.credits)).sum(),
+    })
+}
@@ -0,0 +1,239 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use super::migrations::{self, MigrationReport, SCHEMA_VERSION};
+use super::{Storage, StorageLock};
+use crate::approvals::ApprovalRecord;
+use crate::backup::Backup;
+use crate::courses::Course;
+use crate::enrollment::Enrollment;
+use crate::error::Error;
+use crate::journal::Journal;
+
+/// stores the curriculum, the approved courses and their history as json files
+/// (`courses.json`, `approved.json` and `journal.json`) inside a directory
+/// ## notes
+/// files are written to a temporary file and then renamed over the original,
+/// the previous valid content is kept as a `.bak` copy that is used to recover
+/// the file if it is found corrupted
+///
+/// backups are stored as one json file each in the `backups` directory
+///
+/// the schema version of the layout is kept in `meta.json`, see [`migrations`](super::migrations)
+#[derive(Debug, Clone)]
+pub struct JsonStorage {
+    dir: PathBuf,
+}
+
+impl JsonStorage {
+    /// creates a storage over the given directory
+    /// the directory does not need to exist, it will be created on the first save
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        JsonStorage { dir: dir.into() }
+    }
+
+    /// the directory where the json files are stored
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn courses_path(&self) -> PathBuf {
+        self.dir.join("courses.json")
+    }
+
+    fn approved_path(&self) -> PathBuf {
+        self.dir.join("approved.json")
+    }
+
+    fn enrollment_path(&self) -> PathBuf {
+        self.dir.join("enrollment.json")
+    }
+
+    fn journal_path(&self) -> PathBuf {
+        self.dir.join("journal.json")
+    }
+
+    fn backups_dir(&self) -> PathBuf {
+        self.dir.join("backups")
+    }
+
+    fn ensure_dir(&self) -> Result<(), Error> {
+        if !self.dir.exists() {
+            std::fs::create_dir_all(&self.dir)
+                .map_err(|e| Error::CouldNotCreatePath(self.dir.clone(), e))?;
+        }
+        Ok(())
+    }
+}
+
+impl Storage for JsonStorage {
+    fn has_curriculum(&self) -> Result<bool, Error> {
+        Ok(self.courses_path().exists())
+    }
+
+    fn load_curriculum(&self) -> Result<Vec<Course>, Error> {
+        read_json(&self.courses_path())
+    }
+
+    fn save_curriculum(&self, courses: &[Course]) -> Result<(), Error> {
+        self.ensure_dir()?;
+        // a new layout is created with the current version, while an existing
+        // one keeps its version until it is migrated
+        if !self.dir.join(migrations::METADATA_FILE).exists() && !self.approved_path().exists() {
+            migrations::set_schema_version(&self.dir, SCHEMA_VERSION)?;
+        }
+        write_json(&self.courses_path(), &courses)
+    }
+
+    fn load_records(&self) -> Result<Vec<ApprovalRecord>, Error> {
+        read_json(&self.approved_path())
+    }
+
+    fn save_records(&self, records: &[ApprovalRecord]) -> Result<(), Error> {
+        self.ensure_dir()?;
+        write_json(&self.approved_path(), &records)
+    }
+
+    fn load_enrollment(&self) -> Result<Enrollment, Error> {
+        let path = self.enrollment_path();
+        if !path.exists() && !backup_path(&path).exists() {
+            return Ok(Enrollment::default());
+        }
+        read_json(&path)
+    }
+
+    fn save_enrollment(&self, enrollment: &Enrollment) -> Result<(), Error> {
+        self.ensure_dir()?;
+        write_json(&self.enrollment_path(), enrollment)
+    }
+
+    fn load_journal(&self) -> Result<Journal, Error> {
+        let path = self.journal_path();
+        if !path.exists() && !backup_path(&path).exists() {
+            return Ok(Journal::default());
+        }
+        read_json(&path)
+    }
+
+    fn save_journal(&self, journal: &Journal) -> Result<(), Error> {
+        self.ensure_dir()?;
+        write_json(&self.journal_path(), journal)
+    }
+
+    fn list_backups(&self) -> Result<Vec<String>, Error> {
+        let dir = self.backups_dir();
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+        let entries =
+            std::fs::read_dir(&dir).map_err(|e| Error::CouldNotOpenFile(dir.clone(), e))?;
+        let mut names = Vec::new();
+        for entry in entries {
+            let path = entry
+                .map_err(|e| Error::CouldNotOpenFile(dir.clone(), e))?
+                .path();
+            if path.extension().is_some_and(|e| e == "json") {
+                if let Some(name) = path.file_stem() {
+                    names.push(name.to_string_lossy().to_string());
+                }
+            }
+        }
+        Ok(names)
+    }
+
+    fn load_backup(&self, name: &str) -> Result<Backup, Error> {
+        let path = self.backups_dir().join(format!("{}.json", name));
+        if !path.exists() {
+            return Err(Error::BackupDoesNotExist(name.to_string()));
+        }
+        read_json(&path)
+    }
+
+    fn save_backup(&self, name: &str, backup: &Backup) -> Result<(), Error> {
+        let dir = self.backups_dir();
+        if !dir.exists() {
+            std::fs::create_dir_all(&dir).map_err(|e| Error::CouldNotCreatePath(dir.clone(), e))?;
+        }
+        write_json(&dir.join(format!("{}.json", name)), backup)
+    }
+
+    fn lock(&self, timeout: Duration) -> Result<StorageLock, Error> {
+        self.ensure_dir()?;
+        StorageLock::acquire(&self.dir.join(".lock"), timeout)
+    }
+
+    fn migrate(&self, dry_run: bool) -> Result<MigrationReport, Error> {
+        if !self.has_curriculum()? {
+            return Ok(MigrationReport::up_to_date(SCHEMA_VERSION, dry_run));
+        }
+        migrations::migrate_dir(&self.dir, dry_run)
+    }
+}
+
+/// path of the last good copy of a file
+fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".bak");
+    path.with_file_name(name)
+}
+
+/// path of the temporary file used while writing a file
+fn temporary_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".tmp");
+    path.with_file_name(name)
+}
+
+/// reads a json file, if the file is missing or corrupted the last good copy
+/// is used instead and the file is restored from it
+pub(crate) fn read_json<T: DeserializeOwned>(path: &Path) -> Result<T, Error> {
+    let error = match std::fs::read_to_string(path) {
+        Ok(json) => match serde_json::from_str(&json) {
+            Ok(value) => return Ok(value),
+            Err(e) => Error::JsonDeserialization(e),
+        },
+        Err(e) => Error::CouldNotOpenFile(path.to_path_buf(), e),
+    };
+    // try to recover from the backup, reporting the original error if that fails too
+    let backup = backup_path(path);
+    let json = match std::fs::read_to_string(&backup) {
+        Ok(json) => json,
+        Err(_) => return Err(error),
+    };
+    let value: T = match serde_json::from_str(&json) {
+        Ok(value) => value,
+        Err(_) => return Err(error),
+    };
+    write_atomic(path, json.as_bytes())?;
+    Ok(value)
+}
+
+/// serializes the value and writes it atomically, keeping the previous
+/// content as the last good copy if it is valid json
+pub(crate) fn write_json<T: Serialize + ?Sized>(path: &Path, value: &T) -> Result<(), Error> {
+    let json = serde_json::to_string(value).map_err(Error::JsonSerialization)?;
+    if let Ok(previous) = std::fs::read_to_string(path) {
+        if serde_json::from_str::<serde_json::Value>(&previous).is_ok() {
+            let backup = backup_path(path);
+            write_atomic(&backup, previous.as_bytes())?;
+        }
+    }
+    write_atomic(path, json.as_bytes())
+}
+
+/// writes the contents to a temporary file that is then renamed over the
+/// destination, so the destination is either the old or the new content
+fn write_atomic(path: &Path, contents: &[u8]) -> Result<(), Error> {
+    let temporary = temporary_path(path);
+    let mut file = std::fs::File::create(&temporary)
+        .map_err(|e| Error::CouldNotCreateFile(temporary.clone(), e))?;
+    file.write_all(contents)
+        .and_then(|_| file.sync_all())
+        .map_err(|e| Error::CouldNotCreateFile(temporary.clone(), e))?;
+    drop(file);
+    std::fs::rename(&temporary, path).map_err(|e| Error::CouldNotCreateFile(path.to_path_buf(), e))
+}
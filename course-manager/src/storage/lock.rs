@@ -0,0 +1,57 @@
+use std::fs::File;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use fs2::FileExt;
+
+use crate::error::Error;
+
+/// how often the lock is retried while waiting for another process to release it
+const RETRY_INTERVAL: Duration = Duration::from_millis(50);
+
+/// exclusive advisory lock over a storage, released when dropped
+#[derive(Debug)]
+pub struct StorageLock {
+    file: Option<File>,
+}
+
+impl StorageLock {
+    /// a lock that does not hold anything, for storages that do not
+    /// need to be protected against other processes
+    pub fn none() -> Self {
+        StorageLock { file: None }
+    }
+
+    /// locks the given lock file, creating it if needed
+    /// if another process holds the lock, it is retried until the timeout elapses
+    pub fn acquire(path: &Path, timeout: Duration) -> Result<Self, Error> {
+        let file = File::options()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(path)
+            .map_err(|e| Error::CouldNotCreateFile(path.to_path_buf(), e))?;
+        let start = Instant::now();
+        loop {
+            match file.try_lock_exclusive() {
+                Ok(()) => return Ok(StorageLock { file: Some(file) }),
+                Err(e) if e.kind() == fs2::lock_contended_error().kind() => {
+                    if start.elapsed() >= timeout {
+                        return Err(Error::StorageLocked(path.to_path_buf()));
+                    }
+                    std::thread::sleep(RETRY_INTERVAL);
+                }
+                Err(e) => return Err(Error::CouldNotLock(path.to_path_buf(), e)),
+            }
+        }
+    }
+}
+
+impl Drop for StorageLock {
+    fn drop(&mut self) {
+        if let Some(file) = &self.file {
+            // the lock is released anyway when the file is closed
+            let _ = file.unlock();
+        }
+    }
+}
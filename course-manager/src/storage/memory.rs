@@ -0,0 +1,93 @@
+use std::sync::Mutex;
+
+use super::Storage;
+use crate::approvals::ApprovalRecord;
+use crate::backup::Backup;
+use crate::courses::Course;
+use crate::enrollment::Enrollment;
+use crate::error::Error;
+use crate::journal::Journal;
+
+/// keeps the curriculum, the approved courses and their history in memory
+/// nothing is persisted, this is useful for tests or for short lived sessions
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    curriculum: Mutex<Option<Vec<Course>>>,
+    approved: Mutex<Vec<ApprovalRecord>>,
+    enrollment: Mutex<Enrollment>,
+    journal: Mutex<Journal>,
+    backups: Mutex<Vec<(String, Backup)>>,
+}
+
+impl MemoryStorage {
+    pub fn new() -> Self {
+        MemoryStorage::default()
+    }
+}
+
+impl Storage for MemoryStorage {
+    fn has_curriculum(&self) -> Result<bool, Error> {
+        Ok(self.curriculum.lock().unwrap().is_some())
+    }
+
+    fn load_curriculum(&self) -> Result<Vec<Course>, Error> {
+        self.curriculum
+            .lock()
+            .unwrap()
+            .clone()
+            .ok_or(Error::NotInitialized)
+    }
+
+    fn save_curriculum(&self, courses: &[Course]) -> Result<(), Error> {
+        *self.curriculum.lock().unwrap() = Some(courses.to_vec());
+        Ok(())
+    }
+
+    fn load_records(&self) -> Result<Vec<ApprovalRecord>, Error> {
+        Ok(self.approved.lock().unwrap().clone())
+    }
+
+    fn save_records(&self, records: &[ApprovalRecord]) -> Result<(), Error> {
+        *self.approved.lock().unwrap() = records.to_vec();
+        Ok(())
+    }
+
+    fn load_enrollment(&self) -> Result<Enrollment, Error> {
+        Ok(self.enrollment.lock().unwrap().clone())
+    }
+
+    fn save_enrollment(&self, enrollment: &Enrollment) -> Result<(), Error> {
+        *self.enrollment.lock().unwrap() = enrollment.clone();
+        Ok(())
+    }
+
+    fn load_journal(&self) -> Result<Journal, Error> {
+        Ok(self.journal.lock().unwrap().clone())
+    }
+
+    fn save_journal(&self, journal: &Journal) -> Result<(), Error> {
+        *self.journal.lock().unwrap() = journal.clone();
+        Ok(())
+    }
+
+    fn list_backups(&self) -> Result<Vec<String>, Error> {
+        let backups = self.backups.lock().unwrap();
+        Ok(backups.iter().map(|(name, _)| name.clone()).collect())
+    }
+
+    fn load_backup(&self, name: &str) -> Result<Backup, Error> {
+        let backups = self.backups.lock().unwrap();
+        backups
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, backup)| backup.clone())
+            .ok_or(Error::BackupDoesNotExist(name.to_string()))
+    }
+
+    fn save_backup(&self, name: &str, backup: &Backup) -> Result<(), Error> {
+        let mut backups = self.backups.lock().unwrap();
+        backups.retain(|(n, _)| n != name);
+        backups.push((name.to_string(), backup.clone()));
+        Ok(())
+    }
+}
@@ -0,0 +1,147 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use super::json::{read_json, write_json};
+use crate::approvals::ApprovalRecord;
+use crate::error::Error;
+
+/// version of the json layout written by this version of the library
+/// layouts without a metadata file are considered to be version 0
+pub const SCHEMA_VERSION: u32 = 2;
+
+/// name of the file that records the schema version of a json directory
+pub(crate) const METADATA_FILE: &str = "meta.json";
+
+/// contents of the metadata file
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Metadata {
+    pub(crate) schema_version: u32,
+}
+
+/// a step that upgrades a json directory from the version `from` to `from + 1`
+pub struct Migration {
+    pub from: u32,
+    pub description: &'static str,
+    /// applies the migration to the directory, returning a description of each change
+    /// nothing must be written when `dry_run` is true
+    apply: fn(&Path, bool) -> Result<Vec<String>, Error>,
+}
+
+/// a migration that was applied, or that would be applied on a dry run
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MigrationStep {
+    pub from: u32,
+    pub to: u32,
+    pub description: String,
+    pub changes: Vec<String>,
+}
+
+/// result of migrating a storage to the current schema version
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MigrationReport {
+    pub from: u32,
+    pub to: u32,
+    pub dry_run: bool,
+    pub steps: Vec<MigrationStep>,
+}
+
+impl MigrationReport {
+    /// a report for a storage that is already at the current version
+    pub fn up_to_date(version: u32, dry_run: bool) -> Self {
+        MigrationReport {
+            from: version,
+            to: version,
+            dry_run,
+            steps: Vec::new(),
+        }
+    }
+
+    pub fn is_up_to_date(&self) -> bool {
+        self.steps.is_empty()
+    }
+}
+
+/// every known migration, ordered by the version they upgrade from
+pub fn registry() -> &'static [Migration] {
+    &[
+        Migration {
+            from: 0,
+            description: "record the schema version in a metadata file",
+            apply: |_, _| Ok(Vec::new()),
+        },
+        Migration {
+            from: 1,
+            description: "store a record with the details of each approved course",
+            apply: approval_records,
+        },
+    ]
+}
+
+/// rewrites the list of approved codes as a list of approval records,
+/// the records of the existing approvals are first attempts without details
+fn approval_records(dir: &Path, dry_run: bool) -> Result<Vec<String>, Error> {
+    let path = dir.join("approved.json");
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    // the records also accept the plain codes of the previous layout
+    let records: Vec<ApprovalRecord> = read_json(&path)?;
+    if !dry_run {
+        write_json(&path, &records)?;
+    }
+    Ok(vec![format!(
+        "convert {} approved course(s) to approval records",
+        records.len()
+    )])
+}
+
+fn metadata_path(dir: &Path) -> PathBuf {
+    dir.join(METADATA_FILE)
+}
+
+/// the schema version of a json directory
+pub(crate) fn schema_version(dir: &Path) -> Result<u32, Error> {
+    let path = metadata_path(dir);
+    if !path.exists() {
+        return Ok(0);
+    }
+    let metadata: Metadata = read_json(&path)?;
+    Ok(metadata.schema_version)
+}
+
+/// records the schema version of a json directory
+pub(crate) fn set_schema_version(dir: &Path, version: u32) -> Result<(), Error> {
+    write_json(
+        &metadata_path(dir),
+        &Metadata {
+            schema_version: version,
+        },
+    )
+}
+
+/// upgrades a json directory to the current schema version
+/// the version is recorded after each step, so an interrupted migration resumes where it stopped
+pub(crate) fn migrate_dir(dir: &Path, dry_run: bool) -> Result<MigrationReport, Error> {
+    let from = schema_version(dir)?;
+    if from > SCHEMA_VERSION {
+        return Err(Error::UnsupportedSchemaVersion(from));
+    }
+    let mut report = MigrationReport::up_to_date(from, dry_run);
+    for migration in registry().iter().filter(|m| m.from >= from) {
+        let to = migration.from + 1;
+        let mut changes = (migration.apply)(dir, dry_run)?;
+        changes.push(format!("set the schema version to {}", to));
+        if !dry_run {
+            set_schema_version(dir, to)?;
+        }
+        report.steps.push(MigrationStep {
+            from: migration.from,
+            to,
+            description: migration.description.to_string(),
+            changes,
+        });
+        report.to = to;
+    }
+    Ok(report)
+}
@@ -0,0 +1,79 @@
+use std::time::Duration;
+
+use crate::approvals::{self, ApprovalRecord};
+use crate::backup::Backup;
+use crate::courses::Course;
+use crate::enrollment::Enrollment;
+use crate::error::Error;
+use crate::journal::Journal;
+
+pub(crate) mod json;
+mod lock;
+mod memory;
+pub mod migrations;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+pub use json::JsonStorage;
+pub use lock::StorageLock;
+pub use memory::MemoryStorage;
+pub use migrations::MigrationReport;
+#[cfg(feature = "sqlite")]
+pub use sqlite::{ApprovalEvent, SqliteStorage};
+
+/// backend where the curriculum, the approved courses and their history are persisted
+/// the methods take `&self` so a storage can be shared, implementations
+/// that keep state in memory are expected to use interior mutability
+pub trait Storage {
+    /// whether a curriculum has been saved, if not the storage requires initialization
+    fn has_curriculum(&self) -> Result<bool, Error>;
+    /// loads the curriculum, the statuses of the courses are not set
+    fn load_curriculum(&self) -> Result<Vec<Course>, Error>;
+    /// replaces the stored curriculum
+    fn save_curriculum(&self, courses: &[Course]) -> Result<(), Error>;
+    /// loads the records of the approved courses, in the order they were approved
+    fn load_records(&self) -> Result<Vec<ApprovalRecord>, Error>;
+    /// replaces the records of the approved courses
+    fn save_records(&self, records: &[ApprovalRecord]) -> Result<(), Error>;
+    /// loads the codes of the approved courses
+    fn load_approved(&self) -> Result<Vec<String>, Error> {
+        Ok(approvals::codes(&self.load_records()?))
+    }
+    /// replaces the codes of the approved courses, keeping the records
+    /// of the courses that were already approved
+    fn save_approved(&self, approved: &[String]) -> Result<(), Error> {
+        // nothing to keep if the approved courses were never saved
+        let records = self.load_records().unwrap_or_default();
+        self.save_records(&approvals::merge(&records, approved))
+    }
+    /// loads the courses in progress and the failed attempts, empty if none was saved
+    fn load_enrollment(&self) -> Result<Enrollment, Error>;
+    /// replaces the courses in progress and the failed attempts
+    fn save_enrollment(&self, enrollment: &Enrollment) -> Result<(), Error>;
+    /// loads the journal of mutations of the approved courses, empty if none was saved
+    fn load_journal(&self) -> Result<Journal, Error>;
+    /// replaces the journal of mutations of the approved courses
+    fn save_journal(&self, journal: &Journal) -> Result<(), Error>;
+    /// the names of the stored backups, in no particular order
+    fn list_backups(&self) -> Result<Vec<String>, Error>;
+    /// loads a backup, failing with `Error::BackupDoesNotExist` if there is none with that name
+    fn load_backup(&self, name: &str) -> Result<Backup, Error>;
+    /// stores a backup, replacing any backup with the same name
+    fn save_backup(&self, name: &str, backup: &Backup) -> Result<(), Error>;
+    /// locks the storage against other processes for the duration of a mutation,
+    /// waiting at most `timeout` for the lock to be released
+    /// by default nothing is locked
+    fn lock(&self, timeout: Duration) -> Result<StorageLock, Error> {
+        let _ = timeout;
+        Ok(StorageLock::none())
+    }
+    /// upgrades the stored data to the current schema version,
+    /// on a dry run the report describes the changes without writing anything
+    /// by default the storage is considered to be up to date
+    fn migrate(&self, dry_run: bool) -> Result<MigrationReport, Error> {
+        Ok(MigrationReport::up_to_date(
+            migrations::SCHEMA_VERSION,
+            dry_run,
+        ))
+    }
+}
@@ -0,0 +1,511 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use super::{JsonStorage, Storage, StorageLock};
+use crate::approvals::ApprovalRecord;
+use crate::backup::Backup;
+use crate::courses::Course;
+use crate::enrollment::Enrollment;
+use crate::error::Error;
+use crate::journal::{Journal, JournalEntry};
+use crate::util;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS metadata (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS courses (
+    position INTEGER PRIMARY KEY,
+    code TEXT NOT NULL,
+    name TEXT NOT NULL,
+    credits INTEGER NOT NULL,
+    requirements TEXT NOT NULL,
+    year INTEGER NOT NULL,
+    semester INTEGER NOT NULL,
+    is_bachelor INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS approved (
+    position INTEGER PRIMARY KEY,
+    code TEXT NOT NULL,
+    grade TEXT,
+    term TEXT,
+    attempt INTEGER NOT NULL DEFAULT 1,
+    notes TEXT
+);
+CREATE TABLE IF NOT EXISTS journal (
+    position INTEGER PRIMARY KEY,
+    timestamp INTEGER NOT NULL,
+    command TEXT NOT NULL,
+    added TEXT NOT NULL,
+    removed TEXT NOT NULL,
+    records TEXT NOT NULL DEFAULT '[]',
+    settled TEXT NOT NULL DEFAULT '[]',
+    previous TEXT NOT NULL DEFAULT '[]'
+);
+CREATE TABLE IF NOT EXISTS backups (
+    name TEXT PRIMARY KEY,
+    data TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS approval_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    timestamp INTEGER NOT NULL,
+    code TEXT NOT NULL,
+    approved INTEGER NOT NULL
+);
+";
+
+/// columns added after the first version of the schema, as (table, column, definition)
+/// databases created before them are altered when opened
+const ADDED_COLUMNS: &[(&str, &str, &str)] = &[
+    ("approved", "grade", "TEXT"),
+    ("approved", "term", "TEXT"),
+    ("approved", "attempt", "INTEGER NOT NULL DEFAULT 1"),
+    ("approved", "notes", "TEXT"),
+    ("journal", "records", "TEXT NOT NULL DEFAULT '[]'"),
+    ("journal", "settled", "TEXT NOT NULL DEFAULT '[]'"),
+    ("journal", "previous", "TEXT NOT NULL DEFAULT '[]'"),
+];
+
+/// tables keyed by code in previous versions, which refused repeated codes
+/// that the json storage accepts, databases created before are rebuilt when opened
+const KEYED_BY_POSITION: &[&str] = &["courses", "approved"];
+
+/// a change of the approval status of a course recorded by the sqlite storage
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ApprovalEvent {
+    /// seconds since the unix epoch
+    pub timestamp: u64,
+    pub code: String,
+    /// whether the course was approved or rejected
+    pub approved: bool,
+}
+
+/// stores the curriculum, the approved courses, the journal and the history of approvals
+/// in a single sqlite database file
+#[derive(Debug)]
+pub struct SqliteStorage {
+    connection: Connection,
+    /// lock file used to protect mutations, none for in memory databases
+    lock_path: Option<PathBuf>,
+}
+
+impl SqliteStorage {
+    /// opens the database at the given path, creating it if it does not exist
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let lock_path = lock_path(path.as_ref());
+        SqliteStorage::from_connection(Connection::open(path)?, Some(lock_path))
+    }
+
+    /// opens a database that lives only in memory
+    pub fn open_in_memory() -> Result<Self, Error> {
+        SqliteStorage::from_connection(Connection::open_in_memory()?, None)
+    }
+
+    fn from_connection(connection: Connection, lock_path: Option<PathBuf>) -> Result<Self, Error> {
+        let renamed = rename_tables_keyed_by_code(&connection)?;
+        connection.execute_batch(SCHEMA)?;
+        add_missing_columns(&connection)?;
+        copy_renamed_tables(&connection, &renamed)?;
+        Ok(SqliteStorage {
+            connection,
+            lock_path,
+        })
+    }
+
+    /// copies the curriculum, the approved courses, the enrollment and the journal
+    /// of an existing json directory
+    /// (usually the `courses` directory) into the database
+    /// this is meant to be done once, the previous content of the database is replaced
+    pub fn import_json_dir<P: AsRef<Path>>(&self, dir: P) -> Result<(), Error> {
+        let source = JsonStorage::new(dir.as_ref());
+        let courses = source.load_curriculum()?;
+        let records = source.load_records()?;
+        self.save_curriculum(&courses)?;
+        self.save_enrollment(&source.load_enrollment()?)?;
+        self.save_journal(&source.load_journal()?)?;
+        self.save_records(&records)
+    }
+
+    /// the recorded approvals and rejections, from the oldest to the newest
+    pub fn approval_history(&self) -> Result<Vec<ApprovalEvent>, Error> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT timestamp, code, approved FROM approval_history ORDER BY id")?;
+        let events = statement
+            .query_map([], |row| {
+                Ok(ApprovalEvent {
+                    timestamp: row.get(0)?,
+                    code: row.get(1)?,
+                    approved: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<ApprovalEvent>, rusqlite::Error>>()?;
+        Ok(events)
+    }
+}
+
+impl Storage for SqliteStorage {
+    fn has_curriculum(&self) -> Result<bool, Error> {
+        let initialized: Option<String> = self
+            .connection
+            .query_row(
+                "SELECT value FROM metadata WHERE key = 'initialized'",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(initialized.is_some())
+    }
+
+    fn load_curriculum(&self) -> Result<Vec<Course>, Error> {
+        if !self.has_curriculum()? {
+            return Err(Error::NotInitialized);
+        }
+        let mut statement = self.connection.prepare(
+            "SELECT code, name, credits, requirements, year, semester, is_bachelor
+            FROM courses ORDER BY position",
+        )?;
+        let rows = statement
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, u8>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, u8>(4)?,
+                    row.get::<_, u8>(5)?,
+                    row.get::<_, bool>(6)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, rusqlite::Error>>()?;
+        let mut courses = Vec::with_capacity(rows.len());
+        for (code, name, credits, requirements, year, semester, is_bachelor) in rows {
+            courses.push(Course {
+                code,
+                name,
+                credits,
+                requirements: serde_json::from_str(&requirements)
+                    .map_err(Error::JsonDeserialization)?,
+                year,
+                semester,
+                is_bachelor,
+                status: None,
+                approval: None,
+            });
+        }
+        Ok(courses)
+    }
+
+    fn save_curriculum(&self, courses: &[Course]) -> Result<(), Error> {
+        let transaction = self.connection.unchecked_transaction()?;
+        transaction.execute("DELETE FROM courses", [])?;
+        {
+            let mut statement = transaction.prepare(
+                "INSERT INTO courses
+                (position, code, name, credits, requirements, year, semester, is_bachelor)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            )?;
+            for (position, course) in courses.iter().enumerate() {
+                let requirements = serde_json::to_string(&course.requirements)
+                    .map_err(Error::JsonSerialization)?;
+                statement.execute(params![
+                    position,
+                    course.code,
+                    course.name,
+                    course.credits,
+                    requirements,
+                    course.year,
+                    course.semester,
+                    course.is_bachelor,
+                ])?;
+            }
+        }
+        transaction.execute(
+            "INSERT OR REPLACE INTO metadata (key, value) VALUES ('initialized', 'true')",
+            [],
+        )?;
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn load_records(&self) -> Result<Vec<ApprovalRecord>, Error> {
+        let mut statement = self
+            .connection
+            .prepare("SELECT code, grade, term, attempt, notes FROM approved ORDER BY position")?;
+        let rows = statement
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, Option<String>>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, u32>(3)?,
+                    row.get::<_, Option<String>>(4)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, rusqlite::Error>>()?;
+        let mut records = Vec::new();
+        for (code, grade, term, attempt, notes) in rows {
+            records.push(ApprovalRecord {
+                code,
+                grade: grade
+                    .map(|g| serde_json::from_str(&g))
+                    .transpose()
+                    .map_err(Error::JsonDeserialization)?,
+                term,
+                attempt,
+                notes,
+            });
+        }
+        Ok(records)
+    }
+
+    fn save_records(&self, records: &[ApprovalRecord]) -> Result<(), Error> {
+        let previous = self.load_approved()?;
+        let previous_set: HashSet<&String> = previous.iter().collect();
+        let current: HashSet<&String> = records.iter().map(|r| &r.code).collect();
+        let timestamp = util::unix_timestamp();
+        let transaction = self.connection.unchecked_transaction()?;
+        transaction.execute("DELETE FROM approved", [])?;
+        {
+            let mut insert = transaction.prepare(
+                "INSERT INTO approved (position, code, grade, term, attempt, notes)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            )?;
+            for (position, record) in records.iter().enumerate() {
+                let grade = record
+                    .grade
+                    .as_ref()
+                    .map(serde_json::to_string)
+                    .transpose()
+                    .map_err(Error::JsonSerialization)?;
+                insert.execute(params![
+                    position,
+                    record.code,
+                    grade,
+                    record.term,
+                    record.attempt,
+                    record.notes,
+                ])?;
+            }
+            // record the difference with the previous state
+            let mut history = transaction.prepare(
+                "INSERT INTO approval_history (timestamp, code, approved) VALUES (?1, ?2, ?3)",
+            )?;
+            let added = records.iter().map(|r| &r.code);
+            for code in added.filter(|c| !previous_set.contains(c)) {
+                history.execute(params![timestamp, code, true])?;
+            }
+            for code in previous.iter().filter(|c| !current.contains(c)) {
+                history.execute(params![timestamp, code, false])?;
+            }
+        }
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn load_enrollment(&self) -> Result<Enrollment, Error> {
+        let data: Option<String> = self
+            .connection
+            .query_row(
+                "SELECT value FROM metadata WHERE key = 'enrollment'",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?;
+        match data {
+            Some(data) => serde_json::from_str(&data).map_err(Error::JsonDeserialization),
+            None => Ok(Enrollment::default()),
+        }
+    }
+
+    fn save_enrollment(&self, enrollment: &Enrollment) -> Result<(), Error> {
+        let data = serde_json::to_string(enrollment).map_err(Error::JsonSerialization)?;
+        self.connection.execute(
+            "INSERT OR REPLACE INTO metadata (key, value) VALUES ('enrollment', ?1)",
+            params![data],
+        )?;
+        Ok(())
+    }
+
+    fn load_journal(&self) -> Result<Journal, Error> {
+        let mut statement = self.connection.prepare(
+            "SELECT timestamp, command, added, removed, records, settled, previous
+            FROM journal ORDER BY position",
+        )?;
+        let rows = statement
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, u64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, String>(2)?,
+                    row.get::<_, String>(3)?,
+                    row.get::<_, String>(4)?,
+                    row.get::<_, String>(5)?,
+                    row.get::<_, String>(6)?,
+                ))
+            })?
+            .collect::<Result<Vec<_>, rusqlite::Error>>()?;
+        let mut journal = Journal::default();
+        for (timestamp, command, added, removed, records, settled, previous) in rows {
+            journal.entries.push(JournalEntry {
+                timestamp,
+                command,
+                added: serde_json::from_str(&added).map_err(Error::JsonDeserialization)?,
+                removed: serde_json::from_str(&removed).map_err(Error::JsonDeserialization)?,
+                records: serde_json::from_str(&records).map_err(Error::JsonDeserialization)?,
+                settled: serde_json::from_str(&settled).map_err(Error::JsonDeserialization)?,
+                previous: serde_json::from_str(&previous).map_err(Error::JsonDeserialization)?,
+            });
+        }
+        let position: Option<String> = self
+            .connection
+            .query_row(
+                "SELECT value FROM metadata WHERE key = 'journal_position'",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?;
+        journal.position = position
+            .and_then(|p| p.parse().ok())
+            .unwrap_or(journal.entries.len())
+            .min(journal.entries.len());
+        Ok(journal)
+    }
+
+    fn save_journal(&self, journal: &Journal) -> Result<(), Error> {
+        let transaction = self.connection.unchecked_transaction()?;
+        transaction.execute("DELETE FROM journal", [])?;
+        {
+            let mut statement = transaction.prepare(
+                "INSERT INTO journal (position, timestamp, command, added, removed, records, settled, previous)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            )?;
+            for (position, entry) in journal.entries.iter().enumerate() {
+                statement.execute(params![
+                    position,
+                    entry.timestamp,
+                    entry.command,
+                    serde_json::to_string(&entry.added).map_err(Error::JsonSerialization)?,
+                    serde_json::to_string(&entry.removed).map_err(Error::JsonSerialization)?,
+                    serde_json::to_string(&entry.records).map_err(Error::JsonSerialization)?,
+                    serde_json::to_string(&entry.settled).map_err(Error::JsonSerialization)?,
+                    serde_json::to_string(&entry.previous).map_err(Error::JsonSerialization)?,
+                ])?;
+            }
+        }
+        transaction.execute(
+            "INSERT OR REPLACE INTO metadata (key, value) VALUES ('journal_position', ?1)",
+            params![journal.position.to_string()],
+        )?;
+        transaction.commit()?;
+        Ok(())
+    }
+
+    fn list_backups(&self) -> Result<Vec<String>, Error> {
+        let mut statement = self.connection.prepare("SELECT name FROM backups")?;
+        let names = statement
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<Vec<String>, rusqlite::Error>>()?;
+        Ok(names)
+    }
+
+    fn load_backup(&self, name: &str) -> Result<Backup, Error> {
+        let data: Option<String> = self
+            .connection
+            .query_row(
+                "SELECT data FROM backups WHERE name = ?1",
+                params![name],
+                |row| row.get(0),
+            )
+            .optional()?;
+        let data = data.ok_or(Error::BackupDoesNotExist(name.to_string()))?;
+        serde_json::from_str(&data).map_err(Error::JsonDeserialization)
+    }
+
+    fn save_backup(&self, name: &str, backup: &Backup) -> Result<(), Error> {
+        let data = serde_json::to_string(backup).map_err(Error::JsonSerialization)?;
+        self.connection.execute(
+            "INSERT OR REPLACE INTO backups (name, data) VALUES (?1, ?2)",
+            params![name, data],
+        )?;
+        Ok(())
+    }
+
+    fn lock(&self, timeout: Duration) -> Result<StorageLock, Error> {
+        match &self.lock_path {
+            Some(path) => StorageLock::acquire(path, timeout),
+            None => Ok(StorageLock::none()),
+        }
+    }
+}
+
+/// names of the columns of a table, empty if it does not exist
+/// along with whether the code is its primary key
+fn table_columns(connection: &Connection, table: &str) -> Result<Vec<(String, bool)>, Error> {
+    let mut statement = connection.prepare(&format!("PRAGMA table_info({})", table))?;
+    let columns = statement
+        .query_map([], |row| {
+            Ok((row.get::<_, String>(1)?, row.get::<_, i64>(5)? > 0))
+        })?
+        .collect::<Result<Vec<_>, rusqlite::Error>>()?;
+    Ok(columns)
+}
+
+/// renames the tables that a previous version keyed by code, so that the schema
+/// creates them again keyed by position, returning the renamed tables
+fn rename_tables_keyed_by_code(connection: &Connection) -> Result<Vec<&'static str>, Error> {
+    let mut renamed = Vec::new();
+    for table in KEYED_BY_POSITION {
+        let columns = table_columns(connection, table)?;
+        if columns.iter().any(|(name, key)| name == "code" && *key) {
+            connection.execute_batch(&format!("ALTER TABLE {0} RENAME TO {0}_by_code", table))?;
+            renamed.push(*table);
+        }
+    }
+    Ok(renamed)
+}
+
+/// moves the rows of the renamed tables into the ones created by the schema
+fn copy_renamed_tables(connection: &Connection, renamed: &[&str]) -> Result<(), Error> {
+    for table in renamed {
+        let columns: Vec<String> = table_columns(connection, &format!("{}_by_code", table))?
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        connection.execute_batch(&format!(
+            "INSERT INTO {0} ({1}) SELECT {1} FROM {0}_by_code ORDER BY position;
+            DROP TABLE {0}_by_code;",
+            table,
+            columns.join(", ")
+        ))?;
+    }
+    Ok(())
+}
+
+/// adds the columns that a database created by a previous version does not have
+fn add_missing_columns(connection: &Connection) -> Result<(), Error> {
+    for (table, column, definition) in ADDED_COLUMNS {
+        let mut statement = connection.prepare(&format!("PRAGMA table_info({})", table))?;
+        let columns = statement
+            .query_map([], |row| row.get::<_, String>(1))?
+            .collect::<Result<Vec<String>, rusqlite::Error>>()?;
+        if !columns.iter().any(|c| c == column) {
+            connection.execute_batch(&format!(
+                "ALTER TABLE {} ADD COLUMN {} {}",
+                table, column, definition
+            ))?;
+        }
+    }
+    Ok(())
+}
+
+/// path of the lock file that protects the given database
+fn lock_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".lock");
+    path.with_file_name(name)
+}
@@ -1,7 +1,1520 @@
 use super::*;
+use crate::approvals::{ApprovalDetails, ApprovalRecord, Grade};
 
 #[test]
 fn it_works() {
     let result = add(2, 2);
     assert_eq!(result, 4);
 }
+
+fn course(code: &str, requirements: &[&str]) -> Course {
+    Course {
+        code: code.to_string(),
+        name: format!("course {}", code),
+        credits: 4,
+        requirements: requirements.iter().map(|r| r.to_string()).collect(),
+        year: 1,
+        semester: 1,
+        is_bachelor: true,
+        status: None,
+        approval: None,
+    }
+}
+
+/// a small curriculum where `C` requires `B` which requires `A`
+fn sample_courses() -> Vec<Course> {
+    vec![course("A", &[]), course("B", &["A"]), course("C", &["B"])]
+}
+
+fn codes(courses: &[Course]) -> Vec<String> {
+    courses.iter().map(|c| c.code.clone()).collect()
+}
+
+fn strings(codes: &[&str]) -> Vec<String> {
+    codes.iter().map(|c| c.to_string()).collect()
+}
+
+#[test]
+fn requires_init_until_initialized() {
+    let dir = tempfile::tempdir().unwrap();
+    let manager = CourseManager::new(dir.path().join("data"));
+    assert!(manager.requires_init().unwrap());
+    manager.initialize_courses(sample_courses()).unwrap();
+    assert!(!manager.requires_init().unwrap());
+    assert!(dir.path().join("data/courses/courses.json").exists());
+}
+
+#[test]
+fn approve_checks_requirements() {
+    let dir = tempfile::tempdir().unwrap();
+    let manager = CourseManager::new(dir.path());
+    manager.initialize_courses(sample_courses()).unwrap();
+    assert!(matches!(
+        manager.approve_courses(&strings(&["B"]), false, false),
+        Err(Error::CourseRequirementsNotMet(_))
+    ));
+    manager
+        .approve_courses(&strings(&["A", "B"]), false, false)
+        .unwrap();
+    let approved = manager
+        .get_courses(Some(vec![CourseStatus::Approved]))
+        .unwrap();
+    assert_eq!(codes(&approved), strings(&["A", "B"]));
+}
+
+#[test]
+fn cascade_approve_and_reject() {
+    let dir = tempfile::tempdir().unwrap();
+    let manager = CourseManager::new(dir.path());
+    manager.initialize_courses(sample_courses()).unwrap();
+    manager
+        .approve_courses(&strings(&["C"]), true, false)
+        .unwrap();
+    let approved = manager
+        .get_courses(Some(vec![CourseStatus::Approved]))
+        .unwrap();
+    assert_eq!(approved.len(), 3);
+    manager
+        .reject_courses(&strings(&["A"]), true, false)
+        .unwrap();
+    let available = manager
+        .get_courses(Some(vec![CourseStatus::Available]))
+        .unwrap();
+    assert_eq!(codes(&available), strings(&["A"]));
+}
+
+#[test]
+fn cascade_reject_of_an_unapproved_course_fails() {
+    let manager = CourseManager::with_storage(storage::MemoryStorage::new());
+    manager.initialize_courses(sample_courses()).unwrap();
+    manager
+        .approve_courses(&strings(&["B"]), true, false)
+        .unwrap();
+    assert!(matches!(
+        manager.reject_courses(&strings(&["C"]), true, false),
+        Err(Error::CourseNotApproved(code)) if code == "C"
+    ));
+    assert!(matches!(
+        manager.plan_rejection(&strings(&["C"]), true, false),
+        Err(Error::CourseNotApproved(_))
+    ));
+    assert_eq!(manager.storage().load_approved().unwrap().len(), 2);
+    // forcing skips the check
+    manager
+        .reject_courses(&strings(&["C"]), true, true)
+        .unwrap();
+}
+
+#[test]
+fn cascade_reject_of_an_unknown_course_fails() {
+    let manager = CourseManager::with_storage(storage::MemoryStorage::new());
+    manager.initialize_courses(sample_courses()).unwrap();
+    manager
+        .approve_courses(&strings(&["A"]), false, false)
+        .unwrap();
+    assert!(matches!(
+        manager.reject_courses(&strings(&["Z"]), true, false),
+        Err(Error::CourseDoesNotExist(code)) if code == "Z"
+    ));
+    assert!(matches!(
+        manager.plan_rejection(&strings(&["Z"]), true, false),
+        Err(Error::CourseDoesNotExist(_))
+    ));
+    assert_eq!(manager.history().unwrap().entries.len(), 1);
+}
+
+#[test]
+fn managers_are_independent() {
+    let first_dir = tempfile::tempdir().unwrap();
+    let second_dir = tempfile::tempdir().unwrap();
+    let first = CourseManager::new(first_dir.path());
+    let second = CourseManager::new(second_dir.path());
+    first.initialize_courses(sample_courses()).unwrap();
+    second.initialize_courses(sample_courses()).unwrap();
+    first
+        .approve_courses(&strings(&["A"]), false, false)
+        .unwrap();
+    let approved = second
+        .get_courses(Some(vec![CourseStatus::Approved]))
+        .unwrap();
+    assert!(approved.is_empty());
+}
+
+#[test]
+fn memory_storage_does_not_touch_the_filesystem() {
+    let manager = CourseManager::with_storage(storage::MemoryStorage::new());
+    assert!(manager.requires_init().unwrap());
+    assert!(matches!(
+        manager.get_courses(None),
+        Err(Error::NotInitialized)
+    ));
+    manager.initialize_courses(sample_courses()).unwrap();
+    manager
+        .approve_courses(&strings(&["B"]), true, false)
+        .unwrap();
+    assert_eq!(
+        manager.storage().load_approved().unwrap().len(),
+        2,
+        "the cascade should approve the requirement too"
+    );
+}
+
+#[test]
+fn json_storage_layout() {
+    let dir = tempfile::tempdir().unwrap();
+    let storage = storage::JsonStorage::new(dir.path());
+    storage.save_curriculum(&sample_courses()).unwrap();
+    storage.save_approved(&strings(&["A"])).unwrap();
+    assert!(dir.path().join("courses.json").exists());
+    assert!(dir.path().join("approved.json").exists());
+    assert_eq!(
+        codes(&storage.load_curriculum().unwrap()),
+        strings(&["A", "B", "C"])
+    );
+    assert_eq!(storage.load_approved().unwrap(), strings(&["A"]));
+}
+
+#[cfg(feature = "sqlite")]
+#[test]
+fn sqlite_storage_keeps_history() {
+    let storage = storage::SqliteStorage::open_in_memory().unwrap();
+    assert!(!storage.has_curriculum().unwrap());
+    storage.save_curriculum(&sample_courses()).unwrap();
+    assert_eq!(
+        codes(&storage.load_curriculum().unwrap()),
+        strings(&["A", "B", "C"])
+    );
+    storage.save_approved(&strings(&["A", "B"])).unwrap();
+    storage.save_approved(&strings(&["A"])).unwrap();
+    assert_eq!(storage.load_approved().unwrap(), strings(&["A"]));
+    let history: Vec<(String, bool)> = storage
+        .approval_history()
+        .unwrap()
+        .into_iter()
+        .map(|e| (e.code, e.approved))
+        .collect();
+    assert_eq!(
+        history,
+        vec![
+            ("A".to_string(), true),
+            ("B".to_string(), true),
+            ("B".to_string(), false)
+        ]
+    );
+}
+
+#[cfg(feature = "sqlite")]
+#[test]
+fn sqlite_databases_without_records_are_upgraded() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("courses.db");
+    {
+        let connection = rusqlite::Connection::open(&path).unwrap();
+        connection
+            .execute_batch(
+                "CREATE TABLE approved (position INTEGER NOT NULL, code TEXT PRIMARY KEY);
+                INSERT INTO approved (position, code) VALUES (0, 'A');",
+            )
+            .unwrap();
+    }
+    let storage = storage::SqliteStorage::open(&path).unwrap();
+    assert_eq!(
+        storage.load_records().unwrap(),
+        vec![ApprovalRecord::new("A")]
+    );
+    let mut record = ApprovalRecord::new("A");
+    record.grade = Some("B+".parse::<Grade>().unwrap());
+    storage.save_records(&[record.clone()]).unwrap();
+    assert_eq!(storage.load_records().unwrap(), vec![record.clone()]);
+    // the table was keyed by code, it is rebuilt to accept repeated codes
+    let repeated = vec![record, ApprovalRecord::new("A")];
+    storage.save_records(&repeated).unwrap();
+    assert_eq!(storage.load_records().unwrap(), repeated);
+}
+
+#[cfg(feature = "sqlite")]
+#[test]
+fn sqlite_storage_imports_json_dir() {
+    let dir = tempfile::tempdir().unwrap();
+    let json = CourseManager::new(dir.path());
+    json.initialize_courses(sample_courses()).unwrap();
+    json.approve_courses(&strings(&["A"]), false, false)
+        .unwrap();
+    json.approve_courses(&strings(&["B"]), false, false)
+        .unwrap();
+    json.undo().unwrap();
+    let storage = storage::SqliteStorage::open(dir.path().join("courses.db")).unwrap();
+    storage.import_json_dir(dir.path().join("courses")).unwrap();
+    let manager = CourseManager::with_storage(storage);
+    assert!(!manager.requires_init().unwrap());
+    let approved = manager
+        .get_courses(Some(vec![CourseStatus::Approved]))
+        .unwrap();
+    assert_eq!(codes(&approved), strings(&["A"]));
+    // the undo and redo history comes along
+    assert_eq!(manager.history().unwrap(), json.history().unwrap());
+    manager.redo().unwrap();
+    let approved = manager
+        .get_courses(Some(vec![CourseStatus::Approved]))
+        .unwrap();
+    assert_eq!(codes(&approved), strings(&["A", "B"]));
+}
+
+/// saves the same data, with repeated codes, and loads it back as json values
+#[cfg(feature = "sqlite")]
+fn storage_round_trip(storage: &dyn storage::Storage) -> serde_json::Value {
+    let mut repeated = course("A", &[]);
+    repeated.name = "Repeated".to_string();
+    let mut courses = sample_courses();
+    courses.push(repeated);
+    let mut graded = ApprovalRecord::new("A");
+    graded.grade = Some("7".parse::<Grade>().unwrap());
+    let records = vec![ApprovalRecord::new("A"), ApprovalRecord::new("B"), graded];
+    let enrollment = enrollment::Enrollment {
+        in_progress: strings(&["C"]),
+        failed: vec![enrollment::FailedAttempt {
+            code: "C".to_string(),
+            grade: None,
+            term: Some("2024-1".to_string()),
+        }],
+    };
+    let mut journal = journal::Journal::default();
+    journal.push(journal::JournalEntry {
+        timestamp: 1,
+        command: "approve A".to_string(),
+        added: strings(&["A"]),
+        removed: Vec::new(),
+        records: vec![ApprovalRecord::new("A")],
+        previous: vec![ApprovalRecord::new("B")],
+        settled: strings(&["A"]),
+    });
+    storage.save_curriculum(&courses).unwrap();
+    storage.save_records(&records).unwrap();
+    storage.save_enrollment(&enrollment).unwrap();
+    storage.save_journal(&journal).unwrap();
+    serde_json::json!({
+        "courses": storage.load_curriculum().unwrap(),
+        "records": storage.load_records().unwrap(),
+        "enrollment": storage.load_enrollment().unwrap(),
+        "journal": storage.load_journal().unwrap(),
+    })
+}
+
+#[cfg(feature = "sqlite")]
+#[test]
+fn json_and_sqlite_storages_keep_the_same_data() {
+    let dir = tempfile::tempdir().unwrap();
+    let json = storage_round_trip(&storage::JsonStorage::new(dir.path()));
+    let sqlite = storage_round_trip(&storage::SqliteStorage::open_in_memory().unwrap());
+    assert_eq!(json, sqlite);
+    assert_eq!(json["courses"].as_array().unwrap().len(), 4);
+    assert_eq!(json["records"].as_array().unwrap().len(), 3);
+}
+
+#[test]
+fn json_storage_recovers_corrupted_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let storage = storage::JsonStorage::new(dir.path());
+    storage.save_curriculum(&sample_courses()).unwrap();
+    storage.save_approved(&strings(&["A"])).unwrap();
+    storage.save_approved(&strings(&["A", "B"])).unwrap();
+    // simulate a write interrupted half way
+    std::fs::write(dir.path().join("approved.json"), "[\"A\", \"B").unwrap();
+    assert_eq!(storage.load_approved().unwrap(), strings(&["A"]));
+    // the main file is restored from the last good copy
+    let restored = std::fs::read_to_string(dir.path().join("approved.json")).unwrap();
+    assert_eq!(restored, "[{\"code\":\"A\",\"attempt\":1}]");
+    assert!(!dir.path().join("approved.json.tmp").exists());
+}
+
+#[test]
+fn mutations_fail_while_the_storage_is_locked() {
+    let dir = tempfile::tempdir().unwrap();
+    let manager = CourseManager::new(dir.path()).with_lock_timeout(Duration::from_millis(100));
+    manager.initialize_courses(sample_courses()).unwrap();
+    let lock = manager.storage().lock(Duration::ZERO).unwrap();
+    assert!(matches!(
+        manager.approve_courses(&strings(&["A"]), false, false),
+        Err(Error::StorageLocked(_))
+    ));
+    drop(lock);
+    manager
+        .approve_courses(&strings(&["A"]), true, false)
+        .unwrap();
+    manager
+        .reject_courses(&strings(&["A"]), true, false)
+        .unwrap();
+}
+
+/// writes a layout as created by the versions without a schema version
+fn write_unversioned_layout(dir: &std::path::Path) {
+    let courses = dir.join("courses");
+    std::fs::create_dir_all(&courses).unwrap();
+    let json = serde_json::to_string(&sample_courses()).unwrap();
+    std::fs::write(courses.join("courses.json"), json).unwrap();
+    std::fs::write(courses.join("approved.json"), "[\"A\"]").unwrap();
+}
+
+#[test]
+fn new_layouts_record_the_schema_version() {
+    let dir = tempfile::tempdir().unwrap();
+    let manager = CourseManager::new(dir.path());
+    manager.initialize_courses(sample_courses()).unwrap();
+    let meta = std::fs::read_to_string(dir.path().join("courses/meta.json")).unwrap();
+    assert_eq!(
+        meta,
+        format!(
+            "{{\"schema_version\":{}}}",
+            storage::migrations::SCHEMA_VERSION
+        )
+    );
+    assert!(manager.migrate(true).unwrap().is_up_to_date());
+}
+
+#[test]
+fn unversioned_layouts_are_migrated_on_first_load() {
+    let dir = tempfile::tempdir().unwrap();
+    write_unversioned_layout(dir.path());
+    let manager = CourseManager::new(dir.path());
+    let report = manager.migrate(true).unwrap();
+    assert_eq!(report.from, 0);
+    assert_eq!(report.to, storage::migrations::SCHEMA_VERSION);
+    assert!(!dir.path().join("courses/meta.json").exists());
+    // reading the history migrates the layout like any other entry point
+    assert!(manager.history().unwrap().entries.is_empty());
+    assert!(dir.path().join("courses/meta.json").exists());
+    let manager = CourseManager::new(dir.path());
+    let approved = manager
+        .get_courses(Some(vec![CourseStatus::Approved]))
+        .unwrap();
+    assert_eq!(codes(&approved), strings(&["A"]));
+    assert!(dir.path().join("courses/meta.json").exists());
+    assert!(manager.migrate(true).unwrap().is_up_to_date());
+}
+
+#[test]
+fn plain_approved_codes_are_migrated_to_records() {
+    let dir = tempfile::tempdir().unwrap();
+    write_unversioned_layout(dir.path());
+    std::fs::write(
+        dir.path().join("courses/meta.json"),
+        "{\"schema_version\":1}",
+    )
+    .unwrap();
+    let manager = CourseManager::new(dir.path());
+    let report = manager.migrate(true).unwrap();
+    assert_eq!(report.from, 1);
+    assert_eq!(
+        std::fs::read_to_string(dir.path().join("courses/approved.json")).unwrap(),
+        "[\"A\"]"
+    );
+    manager.migrate(false).unwrap();
+    let approved = std::fs::read_to_string(dir.path().join("courses/approved.json")).unwrap();
+    let approved: serde_json::Value = serde_json::from_str(&approved).unwrap();
+    assert_eq!(approved, serde_json::json!([{"code": "A", "attempt": 1}]));
+    assert_eq!(
+        manager.approval_records().unwrap(),
+        vec![ApprovalRecord::new("A")]
+    );
+}
+
+#[test]
+fn newer_layouts_are_refused() {
+    let dir = tempfile::tempdir().unwrap();
+    write_unversioned_layout(dir.path());
+    std::fs::write(
+        dir.path().join("courses/meta.json"),
+        "{\"schema_version\":999}",
+    )
+    .unwrap();
+    let manager = CourseManager::new(dir.path());
+    assert!(matches!(
+        manager.get_courses(None),
+        Err(Error::UnsupportedSchemaVersion(999))
+    ));
+}
+
+#[test]
+fn reads_do_not_create_files() {
+    let dir = tempfile::tempdir().unwrap();
+    let manager = CourseManager::new(dir.path());
+    assert!(manager.requires_init().unwrap());
+    assert!(manager.get_courses(None).is_err());
+    assert!(manager.migrate(true).unwrap().is_up_to_date());
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+    // an up to date layout is read without taking the lock
+    manager.initialize_courses(sample_courses()).unwrap();
+    std::fs::remove_file(dir.path().join("courses/.lock")).unwrap();
+    let manager = CourseManager::new(dir.path());
+    assert_eq!(manager.get_courses(None).unwrap().len(), 3);
+    assert!(!dir.path().join("courses/.lock").exists());
+}
+
+#[test]
+fn profiles_keep_separate_data() {
+    let dir = tempfile::tempdir().unwrap();
+    let profiles = profiles::Profiles::new(dir.path());
+    assert_eq!(profiles.list().unwrap(), strings(&["default"]));
+    assert_eq!(profiles.active().unwrap(), "default");
+    profiles.create("double-major").unwrap();
+    assert!(matches!(
+        profiles.create("double-major"),
+        Err(Error::ProfileAlreadyExists(_))
+    ));
+    assert!(matches!(
+        profiles.create("../escape"),
+        Err(Error::InvalidProfileName(_))
+    ));
+    profiles
+        .manager("default")
+        .unwrap()
+        .initialize_courses(sample_courses())
+        .unwrap();
+    let second = profiles.manager("double-major").unwrap();
+    assert!(second.requires_init().unwrap());
+    second.initialize_courses(sample_courses()).unwrap();
+    second
+        .approve_courses(&strings(&["A"]), false, false)
+        .unwrap();
+    // the default profile keeps using the original layout
+    assert!(dir.path().join("courses/courses.json").exists());
+    let approved = profiles
+        .manager("default")
+        .unwrap()
+        .get_courses(Some(vec![CourseStatus::Approved]))
+        .unwrap();
+    assert!(approved.is_empty());
+}
+
+#[test]
+fn profiles_switch_rename_and_delete() {
+    let dir = tempfile::tempdir().unwrap();
+    let profiles = profiles::Profiles::new(dir.path());
+    profiles.create("first").unwrap();
+    profiles.switch("first").unwrap();
+    assert_eq!(profiles.active().unwrap(), "first");
+    profiles.rename("first", "second").unwrap();
+    assert_eq!(profiles.active().unwrap(), "second");
+    assert_eq!(profiles.list().unwrap(), strings(&["default", "second"]));
+    assert!(matches!(
+        profiles.delete("default"),
+        Err(Error::ProtectedProfile(_))
+    ));
+    profiles.delete("second").unwrap();
+    assert_eq!(profiles.active().unwrap(), "default");
+    assert!(matches!(
+        profiles.switch("second"),
+        Err(Error::ProfileDoesNotExist(_))
+    ));
+}
+
+#[test]
+fn profile_names_can_not_escape_the_profiles_dir() {
+    let dir = tempfile::tempdir().unwrap();
+    let profiles = profiles::Profiles::new(dir.path().join("data"));
+    profiles
+        .manager("default")
+        .unwrap()
+        .initialize_courses(sample_courses())
+        .unwrap();
+    profiles.create("first").unwrap();
+    for name in ["..", ".", "a/b", "../profiles/first"] {
+        let invalid =
+            |result: Result<(), Error>| matches!(result, Err(Error::InvalidProfileName(_)));
+        assert!(!profiles.exists(name), "{}", name);
+        assert!(invalid(profiles.switch(name)), "{}", name);
+        assert!(invalid(profiles.delete(name)), "{}", name);
+        assert!(invalid(profiles.rename(name, "other")), "{}", name);
+        assert!(invalid(profiles.rename("first", name)), "{}", name);
+        // the cli --profile flag goes through the manager
+        assert!(invalid(profiles.manager(name).map(|_| ())), "{}", name);
+    }
+    assert_eq!(profiles.active().unwrap(), "default");
+    assert_eq!(profiles.list().unwrap(), strings(&["default", "first"]));
+    assert!(dir.path().join("data/courses/courses.json").exists());
+}
+
+#[test]
+fn undo_and_redo_a_cascading_rejection() {
+    let manager = CourseManager::with_storage(storage::MemoryStorage::new());
+    manager.initialize_courses(sample_courses()).unwrap();
+    manager
+        .approve_courses(&strings(&["C"]), true, false)
+        .unwrap();
+    manager
+        .reject_courses(&strings(&["A"]), true, false)
+        .unwrap();
+    assert!(manager.storage().load_approved().unwrap().is_empty());
+
+    let journal = manager.history().unwrap();
+    assert_eq!(journal.entries.len(), 2);
+    assert_eq!(journal.entries[1].command, "reject --recursive A");
+    assert_eq!(journal.entries[1].removed.len(), 3);
+
+    let undone = manager.undo().unwrap().unwrap();
+    assert_eq!(undone.command, "reject --recursive A");
+    assert_eq!(manager.storage().load_approved().unwrap().len(), 3);
+    manager.redo().unwrap().unwrap();
+    assert!(manager.storage().load_approved().unwrap().is_empty());
+    assert!(manager.redo().unwrap().is_none());
+
+    // a new mutation discards the entries that were undone
+    manager.undo().unwrap();
+    manager.undo().unwrap();
+    assert!(manager.undo().unwrap().is_none());
+    manager
+        .approve_courses(&strings(&["A"]), false, false)
+        .unwrap();
+    let journal = manager.history().unwrap();
+    assert_eq!(journal.entries.len(), 1);
+    assert_eq!(journal.position, 1);
+}
+
+#[test]
+fn failed_mutations_are_not_recorded() {
+    let dir = tempfile::tempdir().unwrap();
+    let manager = CourseManager::new(dir.path());
+    manager.initialize_courses(sample_courses()).unwrap();
+    assert!(manager
+        .approve_courses(&strings(&["C"]), false, false)
+        .is_err());
+    assert!(manager.history().unwrap().entries.is_empty());
+    manager
+        .approve_courses(&strings(&["A"]), false, false)
+        .unwrap();
+    assert!(dir.path().join("courses/journal.json").exists());
+    assert_eq!(manager.history().unwrap().entries.len(), 1);
+}
+
+#[cfg(feature = "sqlite")]
+#[test]
+fn sqlite_storage_keeps_the_journal() {
+    let manager = CourseManager::with_storage(storage::SqliteStorage::open_in_memory().unwrap());
+    manager.initialize_courses(sample_courses()).unwrap();
+    manager
+        .approve_courses(&strings(&["B"]), true, false)
+        .unwrap();
+    manager.undo().unwrap();
+    let journal = manager.history().unwrap();
+    assert_eq!(journal.entries.len(), 1);
+    assert_eq!(journal.position, 0);
+    assert_eq!(journal.entries[0].added.len(), 2);
+}
+
+#[test]
+fn init_backs_up_existing_data() {
+    let dir = tempfile::tempdir().unwrap();
+    let manager = CourseManager::new(dir.path());
+    assert_eq!(manager.initialize_courses(sample_courses()).unwrap(), None);
+    manager
+        .approve_courses(&strings(&["A"]), false, false)
+        .unwrap();
+    let backup = manager
+        .initialize_courses(vec![course("X", &[])])
+        .unwrap()
+        .unwrap();
+    assert!(dir
+        .path()
+        .join(format!("courses/backups/{}.json", backup))
+        .exists());
+    let backups = manager.list_backups().unwrap();
+    assert_eq!(backups.len(), 1);
+    assert_eq!(backups[0].courses, 3);
+    assert_eq!(backups[0].approved, 1);
+
+    // restoring backs up the data being replaced
+    let previous = manager.restore_backup(&backup).unwrap().unwrap();
+    assert_ne!(previous, backup);
+    assert_eq!(manager.list_backups().unwrap().len(), 2);
+    let approved = manager
+        .get_courses(Some(vec![CourseStatus::Approved]))
+        .unwrap();
+    assert_eq!(codes(&approved), strings(&["A"]));
+    assert_eq!(manager.history().unwrap().entries.len(), 1);
+    assert!(matches!(
+        manager.restore_backup("missing"),
+        Err(Error::BackupDoesNotExist(_))
+    ));
+}
+
+#[test]
+fn update_keeps_approvals_of_existing_courses() {
+    let manager = CourseManager::with_storage(storage::MemoryStorage::new());
+    manager
+        .initialize_courses(vec![
+            course("A", &[]),
+            course("B", &["A"]),
+            course("OLD", &[]),
+            course("D", &[]),
+            course("GONE", &[]),
+        ])
+        .unwrap();
+    manager
+        .approve_courses(&strings(&["A", "OLD"]), false, false)
+        .unwrap();
+    manager
+        .fail_courses(&strings(&["GONE"]), None, None)
+        .unwrap();
+    manager
+        .enroll_courses(&strings(&["B", "GONE"]), false)
+        .unwrap();
+    // the new version drops OLD and makes D require a new course
+    let revised = vec![
+        course("A", &[]),
+        course("B", &["A"]),
+        course("NEW", &[]),
+        course("D", &["NEW"]),
+    ];
+    let plan = manager.plan_update(&revised).unwrap();
+    assert_eq!(plan.kept, strings(&["A"]));
+    assert_eq!(plan.orphaned, strings(&["OLD"]));
+    assert_eq!(plan.newly_blocked, strings(&["D"]));
+    assert_eq!(plan.orphaned_enrollment, strings(&["GONE"]));
+    // planning does not write anything
+    assert_eq!(manager.storage().load_approved().unwrap().len(), 2);
+    assert_eq!(manager.enrollment().unwrap().in_progress.len(), 2);
+
+    let report = manager.apply_update(revised).unwrap();
+    assert!(report.backup.is_some());
+    let approved = manager
+        .get_courses(Some(vec![CourseStatus::Approved]))
+        .unwrap();
+    assert_eq!(codes(&approved), strings(&["A"]));
+    let enrollment = manager.enrollment().unwrap();
+    assert_eq!(enrollment.in_progress, strings(&["B"]));
+    assert!(enrollment.failed.is_empty());
+    assert_eq!(manager.list_backups().unwrap().len(), 1);
+}
+
+#[test]
+fn update_carries_approvals_through_equivalences() {
+    let manager = CourseManager::with_storage(storage::MemoryStorage::new());
+    manager
+        .initialize_courses(vec![
+            course("A", &[]),
+            course("B", &[]),
+            course("C", &[]),
+            course("D", &[]),
+        ])
+        .unwrap();
+    manager
+        .approve_courses(&strings(&["A", "B", "C"]), false, false)
+        .unwrap();
+    let equivalences = equivalences::Equivalences::from_json(
+        r#"[
+            {"from": ["A", "B"], "to": ["AB"]},
+            {"from": ["C"], "to": ["C1", "C2"]},
+            {"from": ["D"], "to": ["D1"]}
+        ]"#,
+    )
+    .unwrap();
+    let revised = vec![
+        course("AB", &[]),
+        course("C1", &[]),
+        course("C2", &["C1"]),
+        course("D1", &[]),
+    ];
+    let report = manager.apply_update_with(revised, &equivalences).unwrap();
+    assert!(report.kept.is_empty());
+    assert_eq!(report.mapped, strings(&["AB", "C1", "C2"]));
+    assert!(report.orphaned.is_empty());
+    let approved = manager
+        .get_courses(Some(vec![CourseStatus::Approved]))
+        .unwrap();
+    assert_eq!(codes(&approved), strings(&["AB", "C1", "C2"]));
+}
+
+#[test]
+fn equivalences_apply_in_place() {
+    let manager = CourseManager::with_storage(storage::MemoryStorage::new());
+    manager
+        .initialize_courses(vec![
+            course("A", &[]),
+            course("X", &["Y"]),
+            course("Y", &[]),
+        ])
+        .unwrap();
+    manager
+        .approve_courses(&strings(&["A"]), false, false)
+        .unwrap();
+    let equivalences =
+        equivalences::Equivalences::from_json(r#"[{"from": ["A"], "to": ["X", "MISSING"]}]"#)
+            .unwrap();
+    manager.enroll_courses(&strings(&["X"]), true).unwrap();
+    let added = manager.apply_equivalences(&equivalences).unwrap();
+    assert_eq!(added, strings(&["X"]));
+    // like any other approval, it ends the enrollment
+    assert!(manager.enrollment().unwrap().in_progress.is_empty());
+    let entry = &manager.history().unwrap().entries[1];
+    assert_eq!(entry.command, "equivalences apply");
+    assert_eq!(entry.settled, strings(&["X"]));
+    assert!(manager
+        .apply_equivalences(&equivalences)
+        .unwrap()
+        .is_empty());
+    manager.undo().unwrap();
+    assert_eq!(manager.enrollment().unwrap().in_progress, strings(&["X"]));
+}
+
+#[test]
+fn diff_reports_changed_fields() {
+    let old = vec![course("A", &[]), course("B", &["A"]), course("GONE", &[])];
+    let mut renamed = course("B", &["NEW"]);
+    renamed.name = "renamed".to_string();
+    renamed.credits = 3;
+    renamed.semester = 2;
+    let new = vec![course("A", &[]), renamed, course("NEW", &[])];
+    let diff = diff::diff_curricula(&old, &new);
+    assert_eq!(codes(&diff.added), strings(&["NEW"]));
+    assert_eq!(codes(&diff.removed), strings(&["GONE"]));
+    assert_eq!(diff.changed.len(), 1);
+    assert_eq!(diff.changed[0].code, "B");
+    assert_eq!(
+        diff.changed[0].changes,
+        vec![
+            diff::FieldChange::Name {
+                old: "course B".to_string(),
+                new: "renamed".to_string()
+            },
+            diff::FieldChange::Credits { old: 4, new: 3 },
+            diff::FieldChange::Placement {
+                old_year: 1,
+                old_semester: 1,
+                new_year: 1,
+                new_semester: 2
+            },
+            diff::FieldChange::Requirements {
+                added: strings(&["NEW"]),
+                removed: strings(&["A"])
+            },
+        ]
+    );
+    assert!(diff::diff_curricula(&old, &old).is_empty());
+}
+
+#[test]
+fn validation_reports_every_problem() {
+    assert!(!validation::has_errors(&validation::validate(
+        &sample_courses()
+    )));
+    let mut late = course("LATE", &["A"]);
+    late.semester = 3;
+    let courses = vec![
+        course("A", &[]),
+        course("A", &[]),
+        course("SELF", &["SELF"]),
+        course("X", &["Y", "MISSING"]),
+        course("Y", &["Z"]),
+        course("Z", &["X", "A", "A"]),
+        late,
+    ];
+    let diagnostics = validation::validate(&courses);
+    assert!(validation::has_errors(&diagnostics));
+    let kinds: Vec<&validation::DiagnosticKind> = diagnostics
+        .iter()
+        .filter(|d| d.severity == validation::Severity::Error)
+        .map(|d| &d.kind)
+        .collect();
+    assert_eq!(
+        kinds,
+        vec![
+            &validation::DiagnosticKind::DuplicateCode {
+                code: "A".to_string()
+            },
+            &validation::DiagnosticKind::SemesterOutOfRange {
+                code: "LATE".to_string(),
+                semester: 3
+            },
+            &validation::DiagnosticKind::SelfRequirement {
+                code: "SELF".to_string()
+            },
+            &validation::DiagnosticKind::MissingRequirement {
+                code: "X".to_string(),
+                requirement: "MISSING".to_string()
+            },
+            &validation::DiagnosticKind::Cycle {
+                courses: strings(&["X", "Y", "Z"])
+            },
+        ]
+    );
+    // same semester requirements and repeated requirements are only warnings
+    assert!(diagnostics
+        .iter()
+        .any(|d| d.severity == validation::Severity::Warning
+            && d.kind
+                == validation::DiagnosticKind::DuplicateRequirement {
+                    code: "Z".to_string(),
+                    requirement: "A".to_string()
+                }));
+}
+
+#[test]
+fn graph_closures_and_order() {
+    // a diamond, D requires B and C which both require A
+    let courses = vec![
+        course("D", &["B", "C"]),
+        course("B", &["A"]),
+        course("C", &["A", "MISSING"]),
+        course("A", &[]),
+    ];
+    let graph = graph::PrerequisiteGraph::new(&courses);
+    assert_eq!(graph.len(), 4);
+    assert_eq!(graph.required_closure(&["D"]), strings(&["B", "C", "A"]));
+    assert_eq!(graph.dependent_closure(&["A"]), strings(&["B", "C", "D"]));
+    assert_eq!(graph.dependents("A"), strings(&["B", "C"]));
+    assert_eq!(
+        graph.topological_order(&["D", "C", "B", "A"]).unwrap(),
+        strings(&["A", "C", "B", "D"])
+    );
+    assert!(graph.cycles().is_empty());
+    assert!(graph.cycle_through("D").is_none());
+}
+
+#[test]
+fn cycles_are_reported_instead_of_overflowing() {
+    let manager = CourseManager::with_storage(storage::MemoryStorage::new());
+    manager
+        .initialize_courses(vec![
+            course("A", &[]),
+            course("X", &["Y", "A"]),
+            course("Y", &["Z"]),
+            course("Z", &["X"]),
+            course("W", &["X"]),
+        ])
+        .unwrap();
+    let graph = graph::PrerequisiteGraph::new(&manager.get_courses(None).unwrap());
+    assert_eq!(graph.cycles(), vec![strings(&["X", "Y", "Z"])]);
+    assert_eq!(graph.cycle_through("Y").unwrap(), strings(&["Y", "Z", "X"]));
+    assert!(graph.cycle_through("W").is_none());
+    match manager.approve_courses(&strings(&["W"]), true, false) {
+        Err(Error::PrerequisiteCycle(cycle)) => assert_eq!(cycle.len(), 3),
+        other => panic!("unexpected result {:?}", other),
+    }
+    assert!(manager
+        .get_courses(Some(vec![CourseStatus::Approved]))
+        .unwrap()
+        .is_empty());
+    // the cascade of a rejection goes around the cycle once
+    manager
+        .approve_courses(&strings(&["A", "X", "Y", "Z", "W"]), false, true)
+        .unwrap();
+    manager
+        .reject_courses(&strings(&["Z"]), true, false)
+        .unwrap();
+    assert_eq!(
+        codes(
+            &manager
+                .get_courses(Some(vec![CourseStatus::Approved]))
+                .unwrap()
+        ),
+        strings(&["A"])
+    );
+}
+
+#[test]
+fn long_requirement_chains_do_not_overflow() {
+    let count = 100_000;
+    let mut courses = vec![course("C0", &[])];
+    for i in 1..count {
+        courses.push(course(&format!("C{}", i), &[&format!("C{}", i - 1)]));
+    }
+    let graph = graph::PrerequisiteGraph::new(&courses);
+    let last = format!("C{}", count - 1);
+    let required = graph.required_closure(&[&last]);
+    assert_eq!(required.len(), count - 1);
+    assert_eq!(graph.dependent_closure(&["C0"]).len(), count - 1);
+    let order = graph.topological_order(&required).unwrap();
+    assert_eq!(order.first().unwrap(), "C0");
+    assert!(graph.cycles().is_empty());
+}
+
+#[test]
+fn explaining_a_long_chain_does_not_overflow() {
+    let manager = CourseManager::with_storage(storage::MemoryStorage::new());
+    let count = 20_000;
+    let mut courses = vec![course("C0", &[])];
+    for i in 1..count {
+        courses.push(course(&format!("C{}", i), &[&format!("C{}", i - 1)]));
+    }
+    manager.initialize_courses(courses).unwrap();
+    let explanation = manager.explain(&format!("C{}", count - 1)).unwrap();
+    assert_eq!(explanation.semesters, Some(count - 1));
+    assert_eq!(explanation.chain.len(), count - 1);
+    assert_eq!(explanation.chain.last().unwrap().depth, count - 1);
+}
+
+#[test]
+fn bulk_approval_of_a_long_chain() {
+    let manager = CourseManager::with_storage(storage::MemoryStorage::new());
+    let count = 5_000;
+    let mut courses = vec![course("C0", &[])];
+    for i in 1..count {
+        courses.push(course(&format!("C{}", i), &[&format!("C{}", i - 1)]));
+    }
+    let all = codes(&courses);
+    manager.initialize_courses(courses).unwrap();
+    manager.approve_courses(&all, false, false).unwrap();
+    // forcing an approval again does not duplicate it
+    manager
+        .approve_courses(&all[count - 1..], true, false)
+        .unwrap();
+    assert_eq!(manager.storage().load_approved().unwrap(), all);
+    manager
+        .reject_courses(&strings(&["C0"]), true, false)
+        .unwrap();
+    assert!(manager.storage().load_approved().unwrap().is_empty());
+}
+
+#[test]
+fn batch_approval_is_all_or_nothing() {
+    let manager = CourseManager::with_storage(storage::MemoryStorage::new());
+    manager.initialize_courses(sample_courses()).unwrap();
+    let expected = vec![
+        batch::BatchIssue::RequirementsNotMet {
+            code: "C".to_string(),
+            missing: strings(&["B"]),
+        },
+        batch::BatchIssue::DoesNotExist {
+            code: "X".to_string(),
+        },
+    ];
+    match manager.approve_batch(
+        &strings(&["C", "X", "A"]),
+        false,
+        false,
+        false,
+        &ApprovalDetails::default(),
+    ) {
+        Err(Error::InvalidBatch(issues)) => assert_eq!(issues, expected),
+        other => panic!("unexpected result {:?}", other),
+    }
+    assert!(manager.storage().load_approved().unwrap().is_empty());
+    assert!(manager.history().unwrap().entries.is_empty());
+    // a partial batch applies the valid subset
+    let report = manager
+        .approve_batch(
+            &strings(&["C", "X", "A"]),
+            false,
+            false,
+            true,
+            &ApprovalDetails::default(),
+        )
+        .unwrap();
+    assert_eq!(report.approved, strings(&["A"]));
+    assert_eq!(report.issues, expected);
+    assert_eq!(manager.storage().load_approved().unwrap(), strings(&["A"]));
+    assert_eq!(
+        manager.history().unwrap().entries[0].command,
+        "approve --partial C X A"
+    );
+    // requirements approved earlier in the same batch count
+    match manager.approve_batch(
+        &strings(&["A", "B", "C"]),
+        false,
+        false,
+        false,
+        &ApprovalDetails::default(),
+    ) {
+        Err(Error::InvalidBatch(issues)) => assert_eq!(
+            issues,
+            vec![batch::BatchIssue::AlreadyApproved {
+                code: "A".to_string()
+            }]
+        ),
+        other => panic!("unexpected result {:?}", other),
+    }
+    let report = manager
+        .approve_batch(
+            &strings(&["B", "C"]),
+            false,
+            false,
+            false,
+            &ApprovalDetails::default(),
+        )
+        .unwrap();
+    assert_eq!(report.approved, strings(&["B", "C"]));
+    assert!(report.issues.is_empty());
+}
+
+#[test]
+fn batch_approval_ignores_the_argument_order() {
+    let manager = CourseManager::with_storage(storage::MemoryStorage::new());
+    manager
+        .initialize_courses(vec![
+            course("A", &[]),
+            course("B", &["A"]),
+            course("C", &["B"]),
+            course("D", &["X"]),
+            course("X", &["D"]),
+        ])
+        .unwrap();
+    manager
+        .approve_courses(&strings(&["C", "B", "A"]), false, false)
+        .unwrap();
+    assert_eq!(
+        manager.storage().load_approved().unwrap(),
+        strings(&["A", "B", "C"])
+    );
+    // courses requiring each other can not be approved, but do not stop the batch
+    let report = manager
+        .approve_batch(
+            &strings(&["X", "D"]),
+            false,
+            false,
+            true,
+            &ApprovalDetails::default(),
+        )
+        .unwrap();
+    assert!(report.approved.is_empty());
+    assert_eq!(
+        report
+            .issues
+            .iter()
+            .map(|i| i.code())
+            .collect::<Vec<&str>>(),
+        vec!["X", "D"]
+    );
+}
+
+#[test]
+fn explain_blocked_courses() {
+    let manager = CourseManager::with_storage(storage::MemoryStorage::new());
+    manager
+        .initialize_courses(vec![
+            course("A", &[]),
+            course("B", &["A"]),
+            course("C", &["A"]),
+            course("D", &["B", "C"]),
+            course("E", &["D", "MISSING"]),
+        ])
+        .unwrap();
+    let explanation = manager.explain("D").unwrap();
+    assert_eq!(explanation.status, CourseStatus::Blocked);
+    assert_eq!(explanation.missing, strings(&["B", "C"]));
+    assert_eq!(explanation.semesters, Some(2));
+    let chain: Vec<(&str, usize)> = explanation
+        .chain
+        .iter()
+        .map(|n| (n.code.as_str(), n.depth))
+        .collect();
+    assert_eq!(chain, vec![("B", 1), ("A", 2), ("C", 1), ("A", 2)]);
+    assert_eq!(explanation.chain[0].status, CourseStatus::Blocked);
+    assert_eq!(explanation.chain[1].status, CourseStatus::Available);
+    // the shared requirement has no requirements of its own, so it is not marked
+    assert!(!explanation.chain[3].repeated);
+    // a requirement that does not exist can never be met
+    let explanation = manager.explain("E").unwrap();
+    assert_eq!(explanation.semesters, None);
+    let missing = explanation.chain.iter().find(|n| n.code == "MISSING");
+    assert_eq!(missing.unwrap().name, None);
+    manager
+        .approve_courses(&strings(&["A", "B"]), false, false)
+        .unwrap();
+    let explanation = manager.explain("D").unwrap();
+    assert_eq!(explanation.missing, strings(&["C"]));
+    assert_eq!(explanation.semesters, Some(1));
+    assert_eq!(manager.explain("C").unwrap().semesters, Some(0));
+    assert_eq!(manager.explain("A").unwrap().status, CourseStatus::Approved);
+    assert!(matches!(
+        manager.explain("Z"),
+        Err(Error::CourseDoesNotExist(_))
+    ));
+}
+
+#[test]
+fn course_details_include_related_courses() {
+    let manager = CourseManager::with_storage(storage::MemoryStorage::new());
+    manager
+        .initialize_courses(vec![
+            course("A", &[]),
+            course("B", &["A", "MISSING"]),
+            course("C", &["B"]),
+        ])
+        .unwrap();
+    manager
+        .approve_courses(&strings(&["A"]), false, false)
+        .unwrap();
+    let details = manager.course_details(&strings(&["B", "A"])).unwrap();
+    assert_eq!(details[0].course.code, "B");
+    assert_eq!(details[0].course.status, Some(CourseStatus::Blocked));
+    assert_eq!(
+        details[0].prerequisites,
+        vec![
+            details::RelatedCourse {
+                code: "A".to_string(),
+                name: Some("course A".to_string()),
+                status: Some(CourseStatus::Approved)
+            },
+            details::RelatedCourse {
+                code: "MISSING".to_string(),
+                name: None,
+                status: None
+            },
+        ]
+    );
+    assert_eq!(details[0].dependents[0].code, "C");
+    assert_eq!(details[1].dependents[0].code, "B");
+    let json = serde_json::to_value(&details[0]).unwrap();
+    assert_eq!(json["credits"], 4);
+    assert_eq!(json["dependents"][0]["status"], "Blocked");
+    assert!(matches!(
+        manager.course_details(&strings(&["Z"])),
+        Err(Error::CourseDoesNotExist(_))
+    ));
+}
+
+#[test]
+fn previews_do_not_write() {
+    let manager = CourseManager::with_storage(storage::MemoryStorage::new());
+    manager
+        .initialize_courses(vec![
+            course("A", &[]),
+            course("B", &["A"]),
+            course("C", &["B"]),
+            course("D", &["A"]),
+        ])
+        .unwrap();
+    let report = manager
+        .plan_approval(&strings(&["C"]), true, false, false)
+        .unwrap();
+    assert_eq!(report.approved, strings(&["A", "B", "C"]));
+    assert!(manager.storage().load_approved().unwrap().is_empty());
+    manager
+        .approve_courses(&strings(&["C", "D"]), true, false)
+        .unwrap();
+    assert_eq!(
+        manager
+            .plan_rejection(&strings(&["B"]), true, false)
+            .unwrap(),
+        strings(&["B", "C"])
+    );
+    assert_eq!(manager.storage().load_approved().unwrap().len(), 4);
+    assert!(matches!(
+        manager.plan_rejection(&strings(&["X"]), false, false),
+        Err(Error::CourseDoesNotExist(_))
+    ));
+    assert_eq!(manager.history().unwrap().entries.len(), 1);
+    let dependents = manager.dependents("A").unwrap();
+    let levels: Vec<(&str, usize)> = dependents
+        .iter()
+        .map(|d| (d.code.as_str(), d.depth))
+        .collect();
+    assert_eq!(levels, vec![("B", 1), ("D", 1), ("C", 2)]);
+    assert!(manager.dependents("C").unwrap().is_empty());
+}
+
+#[test]
+fn approvals_record_grade_and_term() {
+    let manager = CourseManager::with_storage(storage::MemoryStorage::new());
+    manager.initialize_courses(sample_courses()).unwrap();
+    let details = ApprovalDetails {
+        grade: Some("9.5".parse::<Grade>().unwrap()),
+        term: Some("2023-1".to_string()),
+        ..Default::default()
+    };
+    manager
+        .approve_batch(&strings(&["B"]), true, false, false, &details)
+        .unwrap();
+    let records = manager.approval_records().unwrap();
+    // only the given course gets the details, its requirements are approved without them
+    assert_eq!(records[0], ApprovalRecord::new("A"));
+    assert_eq!(records[1].grade, Some(Grade::Numeric(9.5)));
+    assert_eq!(records[1].term.as_deref(), Some("2023-1"));
+    assert_eq!(records[1].attempt, 1);
+    let courses = manager
+        .get_courses(Some(vec![CourseStatus::Approved]))
+        .unwrap();
+    assert_eq!(courses[1].approval, Some(records[1].clone()));
+    assert_eq!(
+        "A+".parse::<Grade>().unwrap(),
+        Grade::Letter("A+".to_string())
+    );
+    // rejecting and undoing keeps the recorded details
+    manager
+        .reject_courses(&strings(&["B"]), false, false)
+        .unwrap();
+    manager.undo().unwrap();
+    assert_eq!(manager.approval_records().unwrap(), records);
+    // a forced approval that only changes the details can be undone too
+    let regraded = ApprovalDetails {
+        grade: Some("7".parse::<Grade>().unwrap()),
+        ..Default::default()
+    };
+    manager
+        .approve_batch(&strings(&["B"]), false, true, false, &regraded)
+        .unwrap();
+    assert_eq!(
+        manager.approval_records().unwrap()[1].grade,
+        Some(Grade::Numeric(7.0))
+    );
+    manager.undo().unwrap();
+    assert_eq!(manager.approval_records().unwrap(), records);
+    manager.redo().unwrap();
+    assert_eq!(
+        manager.approval_records().unwrap()[1].grade,
+        Some(Grade::Numeric(7.0))
+    );
+}
+
+#[test]
+fn invalid_grades_are_refused() {
+    for grade in ["banana", "101", "-1", "NaN", "G"] {
+        assert!(
+            matches!(grade.parse::<Grade>(), Err(Error::UnknownGrade(_))),
+            "{}",
+            grade
+        );
+    }
+    assert_eq!(
+        " b+ ".parse::<Grade>().unwrap(),
+        Grade::Letter("b+".to_string())
+    );
+    let manager = CourseManager::with_storage(storage::MemoryStorage::new());
+    manager.initialize_courses(sample_courses()).unwrap();
+    let details = ApprovalDetails {
+        grade: Some(Grade::Letter("banana".to_string())),
+        ..Default::default()
+    };
+    assert!(matches!(
+        manager.approve_batch(&strings(&["A"]), false, false, false, &details),
+        Err(Error::UnknownGrade(_))
+    ));
+    assert!(matches!(
+        manager.fail_courses(&strings(&["A"]), details.grade.clone(), None),
+        Err(Error::UnknownGrade(_))
+    ));
+    assert!(manager.approval_records().unwrap().is_empty());
+    assert!(manager.enrollment().unwrap().failed.is_empty());
+}
+
+#[test]
+fn statistics_weight_grades_by_credits() {
+    let mut b = course("B", &[]);
+    b.credits = 2;
+    let mut c = course("C", &[]);
+    c.year = 2;
+    let manager = CourseManager::with_storage(storage::MemoryStorage::new());
+    manager
+        .initialize_courses(vec![course("A", &[]), b, c, course("D", &[])])
+        .unwrap();
+    let approve = |code: &str, grade: &str, attempt: u32| {
+        let details = ApprovalDetails {
+            grade: Some(grade.parse::<Grade>().unwrap()),
+            attempt: Some(attempt),
+            ..Default::default()
+        };
+        manager
+            .approve_batch(&strings(&[code]), false, false, false, &details)
+            .unwrap();
+    };
+    approve("A", "9", 1);
+    approve("B", "6", 1);
+    approve("C", "8", 2);
+    let statistics = manager.statistics(stats::GradeScale::Ten, false).unwrap();
+    // (9 × 4 + 6 × 2 + 8 × 4) / 10
+    assert_eq!(statistics.overall.average, Some(8.0));
+    assert_eq!(statistics.overall.approved_credits, 10);
+    assert_eq!(statistics.total_credits, 14);
+    assert_eq!(statistics.periods.len(), 2);
+    assert_eq!(statistics.periods[0].average.average, Some(8.0));
+    assert_eq!(statistics.periods[1].year, 2);
+    // the failed first attempt of C counts as a 0
+    let statistics = manager.statistics(stats::GradeScale::Ten, true).unwrap();
+    assert_eq!(statistics.overall.average, Some(80.0 / 14.0));
+    assert_eq!(statistics.overall.failed_attempts, 1);
+    assert!(matches!(
+        manager.statistics(stats::GradeScale::Letter, false),
+        Err(Error::InvalidGrade(code, _)) if code == "A"
+    ));
+    assert_eq!(
+        stats::GradeScale::Hundred.value(&"b".parse::<Grade>().unwrap()),
+        Some(75.0)
+    );
+}
+
+#[test]
+fn enrollment_transitions() {
+    let manager = CourseManager::with_storage(storage::MemoryStorage::new());
+    manager.initialize_courses(sample_courses()).unwrap();
+    let status = |manager: &CourseManager, code: &str| {
+        manager
+            .get_courses(None)
+            .unwrap()
+            .into_iter()
+            .find(|c| c.code == code)
+            .unwrap()
+            .status
+    };
+    assert!(matches!(
+        manager.enroll_courses(&strings(&["B"]), false),
+        Err(Error::CourseRequirementsNotMet(_))
+    ));
+    manager.enroll_courses(&strings(&["A"]), false).unwrap();
+    assert_eq!(status(&manager, "A"), Some(CourseStatus::InProgress));
+    assert!(matches!(
+        manager.enroll_courses(&strings(&["A"]), false),
+        Err(Error::CourseAlreadyInProgress(_))
+    ));
+    let in_progress = manager
+        .get_courses(Some(vec![CourseStatus::InProgress]))
+        .unwrap();
+    assert_eq!(codes(&in_progress), strings(&["A"]));
+    manager
+        .fail_courses(&strings(&["A"]), Some("4".parse::<Grade>().unwrap()), None)
+        .unwrap();
+    assert_eq!(status(&manager, "A"), Some(CourseStatus::Failed));
+    manager.enroll_courses(&strings(&["A"]), false).unwrap();
+    manager.withdraw_courses(&strings(&["A"])).unwrap();
+    assert!(matches!(
+        manager.withdraw_courses(&strings(&["A"])),
+        Err(Error::CourseNotInProgress(_))
+    ));
+    manager.enroll_courses(&strings(&["A"]), false).unwrap();
+    manager
+        .approve_courses(&strings(&["A"]), false, false)
+        .unwrap();
+    // the approval ends the enrollment and counts the failed attempt
+    assert!(manager.enrollment().unwrap().in_progress.is_empty());
+    assert_eq!(manager.approval_records().unwrap()[0].attempt, 2);
+    assert!(matches!(
+        manager.fail_courses(&strings(&["A"]), None, None),
+        Err(Error::CourseAlreadyApproved(_))
+    ));
+    let statistics = manager.statistics(stats::GradeScale::Ten, true).unwrap();
+    assert_eq!(statistics.overall.failed_attempts, 1);
+}
+
+fn approve_and_undo_an_enrolled_course(manager: CourseManager) {
+    manager.initialize_courses(sample_courses()).unwrap();
+    manager.enroll_courses(&strings(&["A"]), false).unwrap();
+    manager
+        .approve_courses(&strings(&["A"]), false, false)
+        .unwrap();
+    assert!(manager.enrollment().unwrap().in_progress.is_empty());
+    assert_eq!(
+        manager.history().unwrap().entries[0].settled,
+        strings(&["A"])
+    );
+    manager.undo().unwrap();
+    let in_progress = manager
+        .get_courses(Some(vec![CourseStatus::InProgress]))
+        .unwrap();
+    assert_eq!(codes(&in_progress), strings(&["A"]));
+    manager.redo().unwrap();
+    assert!(manager.enrollment().unwrap().in_progress.is_empty());
+    let approved = manager
+        .get_courses(Some(vec![CourseStatus::Approved]))
+        .unwrap();
+    assert_eq!(codes(&approved), strings(&["A"]));
+}
+
+#[test]
+fn undoing_an_approval_restores_the_enrollment() {
+    approve_and_undo_an_enrolled_course(CourseManager::with_storage(storage::MemoryStorage::new()));
+    #[cfg(feature = "sqlite")]
+    approve_and_undo_an_enrolled_course(CourseManager::with_storage(
+        storage::SqliteStorage::open_in_memory().unwrap(),
+    ));
+}
+
+#[test]
+fn in_progress_requirements_follow_the_availability_rule() {
+    let manager = CourseManager::with_storage(storage::MemoryStorage::new());
+    manager.initialize_courses(sample_courses()).unwrap();
+    manager.enroll_courses(&strings(&["A"]), false).unwrap();
+    let available = manager
+        .get_courses(Some(vec![CourseStatus::Available]))
+        .unwrap();
+    assert!(available.is_empty());
+    let manager = manager.with_availability_rule(enrollment::AvailabilityRule::InProgress);
+    let available = manager
+        .get_courses(Some(vec![CourseStatus::Available]))
+        .unwrap();
+    assert_eq!(codes(&available), strings(&["B"]));
+    manager.enroll_courses(&strings(&["B"]), false).unwrap();
+    assert!(manager.explain("C").unwrap().missing.is_empty());
+}
+
+#[test]
+fn progress_by_semester_and_degree() {
+    let mut c = course("C", &["B"]);
+    c.is_bachelor = false;
+    c.year = 2;
+    let manager = CourseManager::with_storage(storage::MemoryStorage::new());
+    manager
+        .initialize_courses(vec![course("A", &[]), course("B", &["A"]), c])
+        .unwrap();
+    manager
+        .approve_courses(&strings(&["A"]), false, false)
+        .unwrap();
+    let progress = manager.progress().unwrap();
+    assert_eq!(progress.overall.approved_credits, 4);
+    assert_eq!(progress.overall.total_credits, 12);
+    assert_eq!(progress.periods.len(), 2);
+    assert_eq!(progress.periods[0].tally.percentage, 50.0);
+    assert_eq!(progress.periods[1].tally.percentage, 0.0);
+    let years: Vec<(u8, usize)> = progress
+        .years
+        .iter()
+        .map(|y| (y.year, y.tally.total_courses))
+        .collect();
+    assert_eq!(years, vec![(1, 2), (2, 1)]);
+    assert_eq!(progress.years[0].tally.percentage, 50.0);
+    assert_eq!(progress.bachelor.remaining_courses, 1);
+    assert_eq!(progress.licentiate.remaining_courses, 1);
+    assert_eq!(progress.available_courses, 1);
+}
+
+#[test]
+fn planner_respects_requirements_credits_and_parity() {
+    let mut b = course("B", &["A"]);
+    b.semester = 2;
+    let mut c = course("C", &["A"]);
+    c.semester = 2;
+    let mut big = course("BIG", &[]);
+    big.credits = 12;
+    let manager = CourseManager::with_storage(storage::MemoryStorage::new());
+    manager
+        .initialize_courses(vec![
+            course("A", &[]),
+            b,
+            c,
+            course("D", &["B", "C"]),
+            big,
+            course("E", &["MISSING"]),
+        ])
+        .unwrap();
+    let plan = manager.plan_semesters(8, 1).unwrap();
+    let terms: Vec<(usize, u8, Vec<String>)> = plan
+        .terms
+        .iter()
+        .map(|t| {
+            let codes = t.courses.iter().map(|c| c.code.clone()).collect();
+            (t.number, t.semester, codes)
+        })
+        .collect();
+    assert_eq!(
+        terms,
+        vec![
+            (1, 1, strings(&["A"])),
+            (2, 2, strings(&["B", "C"])),
+            (3, 1, strings(&["D"])),
+        ]
+    );
+    assert_eq!(plan.unscheduled, strings(&["BIG", "E"]));
+    // with a lower load B and C need two even terms, leaving an odd term empty
+    manager.enroll_courses(&strings(&["A"]), false).unwrap();
+    let plan = manager.plan_semesters(4, 2).unwrap();
+    let terms: Vec<(usize, usize)> = plan
+        .terms
+        .iter()
+        .map(|t| (t.number, t.courses.len()))
+        .collect();
+    assert_eq!(terms, vec![(1, 1), (2, 0), (3, 1), (4, 1)]);
+    for semester in [0, 3] {
+        assert!(matches!(
+            manager.plan_semesters(4, semester),
+            Err(Error::SemesterOutOfRange(s)) if s == semester
+        ));
+    }
+}
@@ -0,0 +1,149 @@
+use std::collections::HashSet;
+
+use serde::Serialize;
+
+use crate::courses::Course;
+use crate::enrollment::Enrollment;
+use crate::equivalences::Equivalences;
+use crate::error::Error;
+use crate::journal::Journal;
+use crate::{requirements_met, CourseManager};
+
+/// effect of replacing the curriculum with a new version
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct UpdateReport {
+    /// approvals of courses that still exist and are carried over
+    pub kept: Vec<String>,
+    /// courses of the new curriculum approved through equivalences
+    pub mapped: Vec<String>,
+    /// approvals of courses that no longer exist and are not covered
+    /// by an equivalence, they will be dropped
+    pub orphaned: Vec<String>,
+    /// courses in progress or with failed attempts that no longer exist,
+    /// they will be dropped from the enrollment
+    pub orphaned_enrollment: Vec<String>,
+    /// courses that were available and become blocked with the new curriculum
+    pub newly_blocked: Vec<String>,
+    /// name of the backup of the previous data, only set once the update is applied
+    pub backup: Option<String>,
+}
+
+impl UpdateReport {
+    /// every approval of the new curriculum
+    pub fn approved(&self) -> Vec<String> {
+        let mut approved = self.kept.clone();
+        approved.extend(self.mapped.iter().cloned());
+        approved
+    }
+}
+
+/// computes the approvals to carry over to the new curriculum
+fn plan(
+    old: &[Course],
+    new: &[Course],
+    approved: &[String],
+    enrollment: &Enrollment,
+    equivalences: &Equivalences,
+) -> UpdateReport {
+    let new_codes: HashSet<&str> = new.iter().map(|c| c.code.as_str()).collect();
+    let mut orphaned_enrollment: Vec<String> = Vec::new();
+    let enrolled = enrollment.in_progress.iter();
+    for code in enrolled.chain(enrollment.failed.iter().map(|f| &f.code)) {
+        if !new_codes.contains(code.as_str()) && !orphaned_enrollment.contains(code) {
+            orphaned_enrollment.push(code.clone());
+        }
+    }
+    let (kept, dropped): (Vec<String>, Vec<String>) = approved
+        .iter()
+        .cloned()
+        .partition(|code| new_codes.contains(code.as_str()));
+    let mapped: Vec<String> = equivalences
+        .apply(approved)
+        .into_iter()
+        .filter(|code| new_codes.contains(code.as_str()) && !kept.contains(code))
+        .collect();
+    let consumed = equivalences.consumed(approved);
+    let orphaned = dropped
+        .into_iter()
+        .filter(|code| !consumed.contains(code))
+        .collect();
+    let mut new_approved = kept.clone();
+    new_approved.extend(mapped.iter().cloned());
+    let approved_set: HashSet<String> = approved.iter().cloned().collect();
+    let new_approved: HashSet<String> = new_approved.into_iter().collect();
+    let previously_available: HashSet<&str> = old
+        .iter()
+        .filter(|c| !approved_set.contains(&c.code) && requirements_met(c, &approved_set))
+        .map(|c| c.code.as_str())
+        .collect();
+    let newly_blocked = new
+        .iter()
+        .filter(|c| previously_available.contains(c.code.as_str()))
+        .filter(|c| !new_approved.contains(&c.code) && !requirements_met(c, &new_approved))
+        .map(|c| c.code.clone())
+        .collect();
+    UpdateReport {
+        kept,
+        mapped,
+        orphaned,
+        orphaned_enrollment,
+        newly_blocked,
+        backup: None,
+    }
+}
+
+impl CourseManager {
+    /// reports what would happen when replacing the curriculum with a new version,
+    /// without writing anything
+    pub fn plan_update(&self, courses: &[Course]) -> Result<UpdateReport, Error> {
+        self.plan_update_with(courses, &Equivalences::default())
+    }
+
+    /// same as [`plan_update`](CourseManager::plan_update), carrying the approvals
+    /// through the given equivalences
+    pub fn plan_update_with(
+        &self,
+        courses: &[Course],
+        equivalences: &Equivalences,
+    ) -> Result<UpdateReport, Error> {
+        self.ensure_migrated()?;
+        let old = self.storage.load_curriculum()?;
+        let approved = self.storage.load_approved()?;
+        let enrollment = self.storage.load_enrollment()?;
+        Ok(plan(&old, courses, &approved, &enrollment, equivalences))
+    }
+
+    /// replaces the curriculum with a new version, keeping the approvals
+    /// and the enrollment of the courses that still exist
+    /// the previous data is backed up first and the history is reset,
+    /// as the previous entries refer to the old curriculum
+    pub fn apply_update(&self, courses: Vec<Course>) -> Result<UpdateReport, Error> {
+        self.apply_update_with(courses, &Equivalences::default())
+    }
+
+    /// same as [`apply_update`](CourseManager::apply_update), carrying the approvals
+    /// through the given equivalences
+    pub fn apply_update_with(
+        &self,
+        courses: Vec<Course>,
+        equivalences: &Equivalences,
+    ) -> Result<UpdateReport, Error> {
+        self.ensure_migrated()?;
+        let _lock = self.storage.lock(self.lock_timeout)?;
+        let old = self.storage.load_curriculum()?;
+        let approved = self.storage.load_approved()?;
+        let mut enrollment = self.storage.load_enrollment()?;
+        let mut report = plan(&old, &courses, &approved, &enrollment, equivalences);
+        report.backup = Some(self.create_backup_locked()?);
+        self.storage.save_curriculum(&courses)?;
+        self.storage.save_approved(&report.approved())?;
+        if !report.orphaned_enrollment.is_empty() {
+            let orphaned = &report.orphaned_enrollment;
+            enrollment.in_progress.retain(|c| !orphaned.contains(c));
+            enrollment.failed.retain(|f| !orphaned.contains(&f.code));
+            self.storage.save_enrollment(&enrollment)?;
+        }
+        self.storage.save_journal(&Journal::default())?;
+        Ok(report)
+    }
+}
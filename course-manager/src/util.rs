@@ -20,9 +20,7 @@ pub(crate) fn get_app_data_dir() -> Result<std::path::PathBuf, error::Error> {
         // get the app dir from manifest dir
         let dir = env!("CARGO_MANIFEST_DIR");
         match std::path::PathBuf::from(dir).parent() {
-            Some(path) => {
-                return Ok(path.to_path_buf());
-            }
+            Some(path) => Ok(path.to_path_buf()),
             None => Err(error::Error::UserDirNotFound),
         }
     } else {
@@ -41,3 +39,11 @@ pub(crate) fn get_app_data_dir() -> Result<std::path::PathBuf, error::Error> {
         Ok(app_dir)
     }
 }
+
+/// seconds elapsed since the unix epoch
+pub(crate) fn unix_timestamp() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
@@ -0,0 +1,205 @@
+use std::collections::HashSet;
+
+use serde::Serialize;
+
+use crate::courses::Course;
+use crate::graph::{self, PrerequisiteGraph};
+
+/// highest semester a course can be placed in within a year
+pub const MAX_SEMESTER: u8 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    /// the curriculum can be used, but probably contains a mistake
+    Warning,
+    /// the curriculum can not be used reliably
+    Error,
+}
+
+/// a problem found in a curriculum
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DiagnosticKind {
+    /// the code is empty
+    EmptyCode { name: String },
+    /// more than one course uses the same code
+    DuplicateCode { code: String },
+    /// a requirement references a code that does not exist
+    MissingRequirement { code: String, requirement: String },
+    /// a course requires itself
+    SelfRequirement { code: String },
+    /// a requirement is listed more than once
+    DuplicateRequirement { code: String, requirement: String },
+    /// the semester is not between 1 and [`MAX_SEMESTER`]
+    SemesterOutOfRange { code: String, semester: u8 },
+    /// the year is zero
+    YearOutOfRange { code: String, year: u8 },
+    /// the requirements form a cycle, so none of its courses can ever be taken
+    /// the courses are listed in requirement order, each one requires the next
+    /// and the last one requires the first
+    Cycle { courses: Vec<String> },
+    /// a requirement is placed in the same or in a later semester than the course
+    RequirementPlacedLater { code: String, requirement: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    #[serde(flatten)]
+    pub kind: DiagnosticKind,
+}
+
+impl Diagnostic {
+    fn error(kind: DiagnosticKind) -> Self {
+        Diagnostic {
+            severity: Severity::Error,
+            kind,
+        }
+    }
+
+    fn warning(kind: DiagnosticKind) -> Self {
+        Diagnostic {
+            severity: Severity::Warning,
+            kind,
+        }
+    }
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Warning => write!(f, "warning"),
+            Severity::Error => write!(f, "error"),
+        }
+    }
+}
+
+impl std::fmt::Display for DiagnosticKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DiagnosticKind::EmptyCode { name } => write!(f, "course {:?} has an empty code", name),
+            DiagnosticKind::DuplicateCode { code } => {
+                write!(f, "the code {} is used by more than one course", code)
+            }
+            DiagnosticKind::MissingRequirement { code, requirement } => {
+                write!(f, "{} requires {}, which does not exist", code, requirement)
+            }
+            DiagnosticKind::SelfRequirement { code } => write!(f, "{} requires itself", code),
+            DiagnosticKind::DuplicateRequirement { code, requirement } => write!(
+                f,
+                "{} lists the requirement {} more than once",
+                code, requirement
+            ),
+            DiagnosticKind::SemesterOutOfRange { code, semester } => write!(
+                f,
+                "{} is placed in semester {}, which is not between 1 and {}",
+                code, semester, MAX_SEMESTER
+            ),
+            DiagnosticKind::YearOutOfRange { code, year } => {
+                write!(f, "{} is placed in year {}", code, year)
+            }
+            DiagnosticKind::Cycle { courses } => {
+                write!(f, "cyclic requirements: {}", courses.join(" -> "))?;
+                if let Some(first) = courses.first() {
+                    write!(f, " -> {}", first)?;
+                }
+                Ok(())
+            }
+            DiagnosticKind::RequirementPlacedLater { code, requirement } => write!(
+                f,
+                "{} requires {}, which is placed in the same or a later semester",
+                code, requirement
+            ),
+        }
+    }
+}
+
+impl std::fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.severity, self.kind)
+    }
+}
+
+/// whether any of the diagnostics is an error
+pub fn has_errors(diagnostics: &[Diagnostic]) -> bool {
+    diagnostics.iter().any(|d| d.severity == Severity::Error)
+}
+
+/// checks a curriculum for problems, the diagnostics are sorted by severity,
+/// errors first, and then by the order of the courses
+pub fn validate(courses: &[Course]) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let by_code = graph::by_code(courses);
+    let mut seen_codes: HashSet<&str> = HashSet::new();
+    let mut reported_duplicates: HashSet<&str> = HashSet::new();
+    for course in courses {
+        if course.code.trim().is_empty() {
+            diagnostics.push(Diagnostic::error(DiagnosticKind::EmptyCode {
+                name: course.name.clone(),
+            }));
+        }
+        if !seen_codes.insert(&course.code) && reported_duplicates.insert(&course.code) {
+            diagnostics.push(Diagnostic::error(DiagnosticKind::DuplicateCode {
+                code: course.code.clone(),
+            }));
+        }
+        if course.semester == 0 || course.semester > MAX_SEMESTER {
+            diagnostics.push(Diagnostic::error(DiagnosticKind::SemesterOutOfRange {
+                code: course.code.clone(),
+                semester: course.semester,
+            }));
+        }
+        if course.year == 0 {
+            diagnostics.push(Diagnostic::error(DiagnosticKind::YearOutOfRange {
+                code: course.code.clone(),
+                year: course.year,
+            }));
+        }
+    }
+    for course in courses {
+        let mut seen: HashSet<&str> = HashSet::new();
+        for requirement in &course.requirements {
+            if !seen.insert(requirement) {
+                diagnostics.push(Diagnostic::warning(DiagnosticKind::DuplicateRequirement {
+                    code: course.code.clone(),
+                    requirement: requirement.clone(),
+                }));
+                continue;
+            }
+            if *requirement == course.code {
+                diagnostics.push(Diagnostic::error(DiagnosticKind::SelfRequirement {
+                    code: course.code.clone(),
+                }));
+                continue;
+            }
+            match by_code.get(requirement.as_str()) {
+                None => diagnostics.push(Diagnostic::error(DiagnosticKind::MissingRequirement {
+                    code: course.code.clone(),
+                    requirement: requirement.clone(),
+                })),
+                Some(required) => {
+                    if (required.year, required.semester) >= (course.year, course.semester) {
+                        diagnostics.push(Diagnostic::warning(
+                            DiagnosticKind::RequirementPlacedLater {
+                                code: course.code.clone(),
+                                requirement: requirement.clone(),
+                            },
+                        ));
+                    }
+                }
+            }
+        }
+    }
+    // courses requiring themselves are already reported
+    for cycle in PrerequisiteGraph::new(courses)
+        .cycles()
+        .into_iter()
+        .filter(|c| c.len() > 1)
+    {
+        diagnostics.push(Diagnostic::error(DiagnosticKind::Cycle { courses: cycle }));
+    }
+    // stable, so the order of the courses is kept within each severity
+    diagnostics.sort_by_key(|d| std::cmp::Reverse(d.severity));
+    diagnostics
+}